@@ -0,0 +1,455 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+use protobuf::MessageDyn;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin::DumpPluginBlockDataSource;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::ordered_generator;
+use super::row_key::RowKey;
+use super::utility;
+use super::xresloader_bin::XresloaderBinFile;
+
+pub struct BinDiffField {
+    pub path: String,
+    pub old_value: Option<json::JsonValue>,
+    pub new_value: Option<json::JsonValue>,
+}
+
+pub struct BinDiffRow {
+    pub key: Vec<String>,
+    pub row_index: usize,
+    pub data: json::JsonValue,
+}
+
+pub struct BinDiffModifiedRow {
+    pub key: Vec<String>,
+    pub old_row_index: usize,
+    pub new_row_index: usize,
+    pub fields: Vec<BinDiffField>,
+}
+
+pub struct BinDiffResult {
+    pub message_type: String,
+    pub key_fields: Vec<String>,
+    pub old_head: json::JsonValue,
+    pub new_head: json::JsonValue,
+    pub old_file_path: String,
+    pub new_file_path: String,
+    pub added: Vec<BinDiffRow>,
+    pub removed: Vec<BinDiffRow>,
+    pub modified: Vec<BinDiffModifiedRow>,
+    pub unchanged_count: usize,
+}
+
+fn message_to_json(message: &dyn MessageDyn) -> json::JsonValue {
+    let print_options = protobuf_json_mapping::PrintOptions {
+        proto_field_name: true,
+        always_output_default_values: true,
+        ..Default::default()
+    };
+    match protobuf_json_mapping::print_to_string_with_options(message, &print_options) {
+        Ok(output) => match json::parse(&output) {
+            Ok(x) => x,
+            Err(_) => json::JsonValue::String(output),
+        },
+        Err(_) => json::JsonValue::String(protobuf::text_format::print_to_string(message)),
+    }
+}
+
+fn join_diff_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+/// Compare two json values and collect all different leaf values with their paths
+fn diff_json_value(
+    path: &str,
+    old_value: Option<&json::JsonValue>,
+    new_value: Option<&json::JsonValue>,
+    output: &mut Vec<BinDiffField>,
+) {
+    match (old_value, new_value) {
+        (Some(json::JsonValue::Object(old_obj)), Some(json::JsonValue::Object(new_obj))) => {
+            for (key, old_item) in old_obj.iter() {
+                diff_json_value(
+                    &join_diff_path(path, key),
+                    Some(old_item),
+                    new_obj.get(key),
+                    output,
+                );
+            }
+            for (key, new_item) in new_obj.iter() {
+                if old_obj.get(key).is_none() {
+                    diff_json_value(&join_diff_path(path, key), None, Some(new_item), output);
+                }
+            }
+        }
+        (Some(json::JsonValue::Array(old_arr)), Some(json::JsonValue::Array(new_arr))) => {
+            for index in 0..std::cmp::max(old_arr.len(), new_arr.len()) {
+                diff_json_value(
+                    &format!("{}[{}]", path, index),
+                    old_arr.get(index),
+                    new_arr.get(index),
+                    output,
+                );
+            }
+        }
+        (old, new) => {
+            if old != new {
+                output.push(BinDiffField {
+                    path: path.to_string(),
+                    old_value: old.cloned(),
+                    new_value: new.cloned(),
+                });
+            }
+        }
+    }
+}
+
+fn build_row_key(args: &DumpOptions, bin_file: &XresloaderBinFile) -> Result<RowKey, String> {
+    if !args.diff_key_field.is_empty() {
+        let field_names: Vec<String> = args
+            .diff_key_field
+            .iter()
+            .flat_map(|x| x.split(','))
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect();
+        return RowKey::from_field_names(&bin_file.message_descriptor, &field_names);
+    }
+
+    if !args.diff_unique_tag.is_empty() {
+        return RowKey::from_unique_tag(&bin_file.message_descriptor, &args.diff_unique_tag);
+    }
+
+    let ret = RowKey::from_descriptor(&bin_file.message_descriptor);
+    if ret.fields.is_empty() {
+        return Err(format!(
+            "message {} has no field to be used as key",
+            bin_file.message_descriptor.full_name()
+        ));
+    }
+    Ok(ret)
+}
+
+fn key_to_display_string(key_fields: &[String], key: &[String]) -> String {
+    key_fields
+        .iter()
+        .zip(key.iter())
+        .map(|(field, value)| format!("{}={}", field, value))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn key_to_json(key_fields: &[String], key: &[String]) -> json::JsonValue {
+    let mut ret = json::JsonValue::new_object();
+    for (field, value) in key_fields.iter().zip(key.iter()) {
+        let _ = ret.insert(field, value.clone());
+    }
+    ret
+}
+
+fn diff_value_to_string(value: &Option<json::JsonValue>) -> String {
+    match value {
+        Some(x) => json::stringify(x.clone()),
+        None => "(none)".to_string(),
+    }
+}
+
+impl BinDiffResult {
+    pub fn build(
+        old_file: &XresloaderBinFile,
+        new_file: &XresloaderBinFile,
+        row_key: &RowKey,
+    ) -> Self {
+        let mut ret = BinDiffResult {
+            message_type: new_file.data_blocks.data_message_type.clone(),
+            key_fields: row_key.field_names(),
+            old_head: DumpPluginBlockDataSource::new(
+                &old_file.data_blocks,
+                old_file.file_path.clone(),
+            )
+            .to_json(),
+            new_head: DumpPluginBlockDataSource::new(
+                &new_file.data_blocks,
+                new_file.file_path.clone(),
+            )
+            .to_json(),
+            old_file_path: old_file.file_path.clone(),
+            new_file_path: new_file.file_path.clone(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+            unchanged_count: 0,
+        };
+
+        // Rows with duplicated keys are matched by the order they appear
+        let mut old_rows: HashMap<Vec<String>, VecDeque<usize>> = HashMap::new();
+        for (index, message) in old_file.rows.iter().enumerate() {
+            old_rows
+                .entry(row_key.values(message.as_ref()))
+                .or_default()
+                .push_back(index);
+        }
+
+        let mut matched_old_rows = vec![false; old_file.rows.len()];
+        for (new_index, new_message) in new_file.rows.iter().enumerate() {
+            let key = row_key.values(new_message.as_ref());
+            let old_index = old_rows.get_mut(&key).and_then(|x| x.pop_front());
+            let old_index = match old_index {
+                Some(x) => x,
+                None => {
+                    ret.added.push(BinDiffRow {
+                        key,
+                        row_index: new_index + 1,
+                        data: message_to_json(new_message.as_ref()),
+                    });
+                    continue;
+                }
+            };
+            matched_old_rows[old_index] = true;
+
+            if old_file.data_blocks.data_block[old_index]
+                == new_file.data_blocks.data_block[new_index]
+            {
+                ret.unchanged_count += 1;
+                continue;
+            }
+
+            let mut fields = Vec::new();
+            diff_json_value(
+                "",
+                Some(&message_to_json(old_file.rows[old_index].as_ref())),
+                Some(&message_to_json(new_message.as_ref())),
+                &mut fields,
+            );
+            if fields.is_empty() {
+                ret.unchanged_count += 1;
+            } else {
+                ret.modified.push(BinDiffModifiedRow {
+                    key,
+                    old_row_index: old_index + 1,
+                    new_row_index: new_index + 1,
+                    fields,
+                });
+            }
+        }
+
+        for (old_index, old_message) in old_file.rows.iter().enumerate() {
+            if matched_old_rows[old_index] {
+                continue;
+            }
+            ret.removed.push(BinDiffRow {
+                key: row_key.values(old_message.as_ref()),
+                row_index: old_index + 1,
+                data: message_to_json(old_message.as_ref()),
+            });
+        }
+
+        ret
+    }
+
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut ret = json::JsonValue::new_object();
+        let _ = ret.insert("message_type", self.message_type.clone());
+        let _ = ret.insert("key_fields", {
+            let mut key_fields = json::JsonValue::new_array();
+            for field in &self.key_fields {
+                let _ = key_fields.push(field.clone());
+            }
+            key_fields
+        });
+        let _ = ret.insert("old", self.old_head.clone());
+        let _ = ret.insert("new", self.new_head.clone());
+
+        let mut summary = json::JsonValue::new_object();
+        let _ = summary.insert("added", self.added.len());
+        let _ = summary.insert("removed", self.removed.len());
+        let _ = summary.insert("modified", self.modified.len());
+        let _ = summary.insert("unchanged", self.unchanged_count);
+        let _ = ret.insert("summary", summary);
+
+        let rows_to_json = |rows: &Vec<BinDiffRow>| {
+            let mut json_rows = json::JsonValue::new_array();
+            for row in rows {
+                let mut json_row = json::JsonValue::new_object();
+                let _ = json_row.insert("key", key_to_json(&self.key_fields, &row.key));
+                let _ = json_row.insert("row", row.row_index);
+                let _ = json_row.insert("data", row.data.clone());
+                let _ = json_rows.push(json_row);
+            }
+            json_rows
+        };
+        let _ = ret.insert("added", rows_to_json(&self.added));
+        let _ = ret.insert("removed", rows_to_json(&self.removed));
+
+        let mut json_modified = json::JsonValue::new_array();
+        for row in &self.modified {
+            let mut json_row = json::JsonValue::new_object();
+            let _ = json_row.insert("key", key_to_json(&self.key_fields, &row.key));
+            let _ = json_row.insert("old_row", row.old_row_index);
+            let _ = json_row.insert("new_row", row.new_row_index);
+            let mut json_fields = json::JsonValue::new_array();
+            for field in &row.fields {
+                let mut json_field = json::JsonValue::new_object();
+                let _ = json_field.insert("path", field.path.clone());
+                let _ = json_field.insert(
+                    "old",
+                    field.old_value.clone().unwrap_or(json::JsonValue::Null),
+                );
+                let _ = json_field.insert(
+                    "new",
+                    field.new_value.clone().unwrap_or(json::JsonValue::Null),
+                );
+                let _ = json_fields.push(json_field);
+            }
+            let _ = json_row.insert("fields", json_fields);
+            let _ = json_modified.push(json_row);
+        }
+        let _ = ret.insert("modified", json_modified);
+
+        ret
+    }
+
+    pub fn to_text(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        ret.push(format!("--- {}", self.old_file_path));
+        ret.push(format!("+++ {}", self.new_file_path));
+        ret.push(format!(
+            "message type: {}, key: {}",
+            self.message_type,
+            self.key_fields.join(", ")
+        ));
+
+        for row in &self.removed {
+            ret.push(format!(
+                "- [{}] row {}: {}",
+                key_to_display_string(&self.key_fields, &row.key),
+                row.row_index,
+                ordered_generator::stringify(row.data.clone())
+            ));
+        }
+
+        for row in &self.added {
+            ret.push(format!(
+                "+ [{}] row {}: {}",
+                key_to_display_string(&self.key_fields, &row.key),
+                row.row_index,
+                ordered_generator::stringify(row.data.clone())
+            ));
+        }
+
+        for row in &self.modified {
+            ret.push(format!(
+                "~ [{}] row {} -> {}",
+                key_to_display_string(&self.key_fields, &row.key),
+                row.old_row_index,
+                row.new_row_index
+            ));
+            for field in &row.fields {
+                ret.push(format!(
+                    "    {}: {} -> {}",
+                    field.path,
+                    diff_value_to_string(&field.old_value),
+                    diff_value_to_string(&field.new_value)
+                ));
+            }
+        }
+
+        ret.push(format!(
+            "{} added, {} removed, {} modified, {} unchanged",
+            self.added.len(),
+            self.removed.len(),
+            self.modified.len(),
+            self.unchanged_count
+        ));
+
+        ret
+    }
+}
+
+fn write_to_file(output_file: &str, content: &[u8]) -> bool {
+    match utility::create_output_file(output_file) {
+        Ok(mut f) => {
+            if let Err(e) = f.write_all(content) {
+                error!("Try to write diff result to {} failed, {}", output_file, e);
+                return false;
+            }
+            true
+        }
+        Err(e) => {
+            error!(
+                "Try to open {} to write diff result failed, {}",
+                output_file, e
+            );
+            false
+        }
+    }
+}
+
+/// Compare rows of --diff-old-bin and --diff-new-bin, return true if there is any error
+pub fn run(args: &DumpOptions, desc_index: &mut FileDescriptorIndex) -> bool {
+    if args.diff_old_bin.is_empty() || args.diff_new_bin.is_empty() {
+        error!("Both --diff-old-bin and --diff-new-bin are required to run diff mode");
+        return true;
+    }
+
+    let old_file = match XresloaderBinFile::load(&args.diff_old_bin, desc_index) {
+        Ok(x) => x,
+        Err(_) => return true,
+    };
+    let new_file = match XresloaderBinFile::load(&args.diff_new_bin, desc_index) {
+        Ok(x) => x,
+        Err(_) => return true,
+    };
+
+    if old_file.data_blocks.data_message_type != new_file.data_blocks.data_message_type {
+        error!(
+            "Can not diff {} and {}, message type {} is not the same as {}",
+            &old_file.file_path,
+            &new_file.file_path,
+            &old_file.data_blocks.data_message_type,
+            &new_file.data_blocks.data_message_type
+        );
+        return true;
+    }
+
+    let row_key = match build_row_key(args, &new_file) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Build key of diff rows failed, {}", e);
+            return true;
+        }
+    };
+
+    let diff_result = BinDiffResult::build(&old_file, &new_file, &row_key);
+
+    let mut has_error = false;
+    if !args.output_diff_json.is_empty() {
+        let output = if args.pretty {
+            json::stringify_pretty(diff_result.to_json(), 2)
+        } else {
+            json::stringify(diff_result.to_json())
+        };
+        has_error |= !write_to_file(&args.output_diff_json, output.as_bytes());
+    }
+
+    if !args.output_diff_text.is_empty() {
+        let mut output = diff_result.to_text().join("\n");
+        output.push('\n');
+        has_error |= !write_to_file(&args.output_diff_text, output.as_bytes());
+    }
+
+    if args.output_diff_json.is_empty() && args.output_diff_text.is_empty() && !args.silence {
+        for line in diff_result.to_text() {
+            info!("{}", line);
+        }
+    }
+
+    has_error
+}
@@ -0,0 +1,387 @@
+use protobuf::MessageDyn;
+use protobuf::reflect::MessageDescriptor;
+use xresloader_protocol::proto::pb_header_v3::{Xresloader_data_source, Xresloader_datablocks};
+
+use super::dump_options::DumpOptions;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::hash_code;
+use super::xresloader_bin;
+
+/// Metadata fields written by --output-ndjson, they are not fields of rows
+const ROW_FILE_KEY: &str = "_file";
+const ROW_SHEET_KEY: &str = "_sheet";
+const ROW_INDEX_KEY: &str = "_row";
+const ROW_MESSAGE_TYPE_KEY: &str = "_message_type";
+
+#[derive(Clone, Copy, PartialEq)]
+enum EncodeInputFormat {
+    Json,
+    Ndjson,
+    Text,
+}
+
+/// Row not parsed yet, message type may be unknown before all rows are read
+enum EncodeInputRow {
+    Json(json::JsonValue),
+    Text(String),
+}
+
+#[derive(Default)]
+struct EncodeInput {
+    message_type: String,
+    xres_ver: String,
+    data_ver: String,
+    description: String,
+    data_source: Vec<Xresloader_data_source>,
+    rows: Vec<EncodeInputRow>,
+    /// Data source of each row, set by metadata fields of json rows
+    row_data_source: Vec<Option<(String, String)>>,
+}
+
+fn get_input_format(args: &DumpOptions) -> Result<EncodeInputFormat, String> {
+    let format = if args.encode_format.eq_ignore_ascii_case("auto") {
+        std::path::Path::new(&args.encode_input)
+            .extension()
+            .map(|x| x.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    } else {
+        args.encode_format.to_lowercase()
+    };
+
+    match format.as_str() {
+        "json" => Ok(EncodeInputFormat::Json),
+        "ndjson" | "jsonl" => Ok(EncodeInputFormat::Ndjson),
+        "text" | "txt" | "textproto" | "pbtxt" => Ok(EncodeInputFormat::Text),
+        _ => Err(format!(
+            "unknown format of {}, please use --encode-format json, ndjson or text",
+            args.encode_input
+        )),
+    }
+}
+
+fn json_to_string(value: &json::JsonValue) -> String {
+    if value.is_null() {
+        return String::new();
+    }
+
+    match value.as_str() {
+        Some(x) => x.to_string(),
+        None => value.dump(),
+    }
+}
+
+impl EncodeInput {
+    fn load_header(&mut self, header: &json::JsonValue) {
+        self.message_type = json_to_string(&header["data_message_type"]);
+        self.xres_ver = json_to_string(&header["xres_ver"]);
+        self.data_ver = json_to_string(&header["data_ver"]);
+        self.description = json_to_string(&header["description"]);
+        for source in header["data_source"].members() {
+            let mut data_source = Xresloader_data_source::new();
+            data_source.file = json_to_string(&source["file"]);
+            data_source.sheet = json_to_string(&source["sheet"]);
+            data_source.count = source["count"].as_i32().unwrap_or(0);
+            self.data_source.push(data_source);
+        }
+    }
+
+    fn add_json_row(&mut self, mut row: json::JsonValue) -> Result<(), String> {
+        if !row.is_object() {
+            return Err(format!("row {} is not a json object", self.rows.len() + 1));
+        }
+
+        let message_type = row.remove(ROW_MESSAGE_TYPE_KEY);
+        if let Some(x) = message_type.as_str() {
+            if self.message_type.is_empty() {
+                self.message_type = x.to_string();
+            } else if self.message_type != x {
+                return Err(format!(
+                    "message type {} of row {} is not the same as {}",
+                    x,
+                    self.rows.len() + 1,
+                    self.message_type
+                ));
+            }
+        }
+
+        let file = row.remove(ROW_FILE_KEY);
+        let sheet = row.remove(ROW_SHEET_KEY);
+        row.remove(ROW_INDEX_KEY);
+        if file.is_null() && sheet.is_null() {
+            self.row_data_source.push(None);
+        } else {
+            self.row_data_source
+                .push(Some((json_to_string(&file), json_to_string(&sheet))));
+        }
+
+        self.rows.push(EncodeInputRow::Json(row));
+        Ok(())
+    }
+
+    /// Json document written by --output-json, or an array of rows
+    fn load_json(&mut self, content: &str) -> Result<(), String> {
+        let mut document = json::parse(content).map_err(|e| e.to_string())?;
+        let rows = if document.is_array() {
+            document
+        } else if document.has_key("rows") {
+            self.load_header(&document["header"]);
+            document.remove("rows")
+        } else {
+            return Err("json input should be an array of rows or an object with rows".to_string());
+        };
+
+        for row in rows.members() {
+            self.add_json_row(row.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Json lines written by --output-ndjson
+    fn load_ndjson(&mut self, content: &str) -> Result<(), String> {
+        for (line_index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let row = json::parse(line).map_err(|e| format!("line {}, {}", line_index + 1, e))?;
+            self.add_json_row(row)?;
+        }
+        Ok(())
+    }
+
+    /// Text format rows, separated by lines starting with `---`
+    fn load_text(&mut self, content: &str) {
+        let mut row = String::new();
+        for line in content.lines() {
+            if line.trim_start().starts_with("---") {
+                if !row.trim().is_empty() {
+                    self.rows
+                        .push(EncodeInputRow::Text(std::mem::take(&mut row)));
+                    self.row_data_source.push(None);
+                }
+                row.clear();
+                continue;
+            }
+
+            row.push_str(line);
+            row.push('\n');
+        }
+
+        if !row.trim().is_empty() {
+            self.rows.push(EncodeInputRow::Text(row));
+            self.row_data_source.push(None);
+        }
+    }
+
+    /// Data source with count of rows, rebuilt from metadata of rows if they are set
+    fn build_data_source(&self, input_file: &str) -> Vec<Xresloader_data_source> {
+        if self.row_data_source.iter().any(|x| x.is_some()) {
+            let mut ret: Vec<Xresloader_data_source> = Vec::new();
+            for (file, sheet) in self.row_data_source.iter().flatten() {
+                if let Some(last) = ret.last_mut()
+                    && last.file == *file
+                    && last.sheet == *sheet
+                {
+                    last.count += 1;
+                    continue;
+                }
+
+                let mut data_source = Xresloader_data_source::new();
+                data_source.file = file.clone();
+                data_source.sheet = sheet.clone();
+                data_source.count = 1;
+                ret.push(data_source);
+            }
+
+            let count: usize = ret.iter().map(|x| x.count as usize).sum();
+            if count != self.rows.len() {
+                warn!(
+                    "Only {} of {} rows in {} have {} and {}, count of data source in header will be removed",
+                    count,
+                    self.rows.len(),
+                    input_file,
+                    ROW_FILE_KEY,
+                    ROW_SHEET_KEY
+                );
+                for data_source in ret.iter_mut() {
+                    data_source.count = 0;
+                }
+            }
+            return ret;
+        }
+
+        let mut ret = self.data_source.clone();
+        let count: usize = ret.iter().map(|x| x.count.max(0) as usize).sum();
+        if ret.len() == 1 {
+            ret[0].count = self.rows.len() as i32;
+        } else if !ret.is_empty() && count != self.rows.len() {
+            warn!(
+                "Rows in {} are added or removed, count of data source in header will be removed",
+                input_file
+            );
+            for data_source in ret.iter_mut() {
+                data_source.count = 0;
+            }
+        }
+        ret
+    }
+}
+
+fn parse_row(
+    message_descriptor: &MessageDescriptor,
+    row: &EncodeInputRow,
+) -> Result<Box<dyn MessageDyn>, String> {
+    match row {
+        EncodeInputRow::Json(x) => {
+            let parse_options = protobuf_json_mapping::ParseOptions {
+                ignore_unknown_fields: false,
+                ..Default::default()
+            };
+            protobuf_json_mapping::parse_dyn_from_str_with_options(
+                message_descriptor,
+                &x.dump(),
+                &parse_options,
+            )
+            .map_err(|e| e.to_string())
+        }
+        EncodeInputRow::Text(x) => {
+            let mut message = message_descriptor.new_instance();
+            protobuf::text_format::merge_from_str(message.as_mut(), x)
+                .map_err(|e| e.to_string())?;
+            Ok(message)
+        }
+    }
+}
+
+fn choose_header_value(option_value: &str, input_value: &str, default_value: &str) -> String {
+    if !option_value.is_empty() {
+        option_value.to_string()
+    } else if !input_value.is_empty() {
+        input_value.to_string()
+    } else {
+        default_value.to_string()
+    }
+}
+
+/// Encode rows in json or text format into a binary file of xresloader, return true if there is any error
+pub fn run(args: &DumpOptions, desc_index: &mut FileDescriptorIndex) -> bool {
+    if args.encode_input.is_empty() || args.encode_output.is_empty() {
+        error!("Both --encode-input and --encode-output are required to run encode mode");
+        return true;
+    }
+
+    let format = match get_input_format(args) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("{}", e);
+            return true;
+        }
+    };
+
+    let content = match std::fs::read_to_string(&args.encode_input) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Try to read file {} failed, {}", &args.encode_input, e);
+            return true;
+        }
+    };
+
+    let mut input = EncodeInput::default();
+    let load_result = match format {
+        EncodeInputFormat::Json => input.load_json(&content),
+        EncodeInputFormat::Ndjson => input.load_ndjson(&content),
+        EncodeInputFormat::Text => {
+            input.load_text(&content);
+            Ok(())
+        }
+    };
+    if let Err(e) = load_result {
+        error!("Load rows from {} failed, {}", &args.encode_input, e);
+        return true;
+    }
+
+    let message_type = choose_header_value(&args.encode_message_type, &input.message_type, "");
+    if message_type.is_empty() {
+        error!(
+            "Message type of rows in {} is unknown, please set it by --encode-message-type",
+            &args.encode_input
+        );
+        return true;
+    }
+
+    let message_descriptor = match desc_index.build_message_descriptor(&message_type) {
+        Ok(x) => x,
+        Err(_) => {
+            error!("Build message descriptor {} failed", &message_type);
+            return true;
+        }
+    };
+
+    let mut data_blocks = Xresloader_datablocks::new();
+    for (row_index, row) in input.rows.iter().enumerate() {
+        let message = match parse_row(&message_descriptor, row) {
+            Ok(x) => x,
+            Err(e) => {
+                error!(
+                    "Parse row {} of {} to message {} failed, {}",
+                    row_index + 1,
+                    &args.encode_input,
+                    &message_type,
+                    e
+                );
+                return true;
+            }
+        };
+
+        match message.write_to_bytes_dyn() {
+            Ok(x) => data_blocks.data_block.push(x),
+            Err(e) => {
+                error!(
+                    "Encode row {} of {} failed, {}",
+                    row_index + 1,
+                    &args.encode_input,
+                    e
+                );
+                return true;
+            }
+        }
+    }
+
+    let header = data_blocks.header.mut_or_insert_default();
+    header.xres_ver = choose_header_value(
+        &args.encode_xres_ver,
+        &input.xres_ver,
+        env!("CARGO_PKG_VERSION"),
+    );
+    header.data_ver = choose_header_value(&args.encode_data_ver, &input.data_ver, "1.0.0.0");
+    header.description = choose_header_value(&args.encode_description, &input.description, "");
+    header.count = data_blocks.data_block.len() as u32;
+    header.data_source = input.build_data_source(&args.encode_input);
+    header.hash_code = match hash_code::calculate_hash_code(
+        &args.encode_hash_algorithm,
+        &data_blocks.data_block,
+    ) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Calculate hash code failed, {}", e);
+            return true;
+        }
+    };
+    data_blocks.data_message_type = message_type;
+
+    if xresloader_bin::save_data_blocks(&args.encode_output, &data_blocks).is_err() {
+        return true;
+    }
+
+    if !args.silence {
+        info!(
+            "Encode {} row(s) of {} from {} into {} success",
+            data_blocks.data_block.len(),
+            &data_blocks.data_message_type,
+            &args.encode_input,
+            &args.encode_output
+        );
+    }
+
+    false
+}
@@ -0,0 +1,947 @@
+use std::collections::{HashMap, HashSet};
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{ReflectValueBox, ReflectValueRef, RuntimeFieldType, RuntimeType};
+
+use super::dump_options::DumpOptions;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::hash_code;
+use super::string_table::{self, StringTableFilter};
+use super::utility;
+use super::xresloader_bin::{self, XresloaderBinFile};
+
+#[derive(Clone, Copy, PartialEq)]
+enum LocalizeInputFormat {
+    Json,
+    Csv,
+    Po,
+}
+
+/// Translations of source strings, translations with field full name(msgctxt of PO) are used first
+#[derive(Default)]
+struct LocalizeTranslations {
+    by_field: HashMap<(String, String), String>,
+    by_value: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct LocalizeStats {
+    replaced_count: usize,
+    missing: HashSet<String>,
+}
+
+fn get_input_format(args: &DumpOptions) -> Result<LocalizeInputFormat, String> {
+    let format = if args.localize_format.eq_ignore_ascii_case("auto") {
+        std::path::Path::new(&args.localize_input)
+            .extension()
+            .map(|x| x.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    } else {
+        args.localize_format.to_lowercase()
+    };
+
+    match format.as_str() {
+        "json" => Ok(LocalizeInputFormat::Json),
+        "csv" => Ok(LocalizeInputFormat::Csv),
+        "po" => Ok(LocalizeInputFormat::Po),
+        _ => Err(format!(
+            "unknown format of {}, please use --localize-format json, csv or po",
+            args.localize_input
+        )),
+    }
+}
+
+/// Files may be encoded in UTF-8(with or without BOM) or UTF-16LE with BOM, like --output-string-table-ue-csv
+fn read_translation_file(file_path: &str) -> Result<String, String> {
+    let content = std::fs::read(file_path).map_err(|e| e.to_string())?;
+    if let Some(x) = content.strip_prefix(&[0xff, 0xfe]) {
+        let units: Vec<u16> = x
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c.get(1).copied().unwrap_or(0)]))
+            .collect();
+        return String::from_utf16(&units).map_err(|e| e.to_string());
+    }
+
+    let content = content
+        .strip_prefix(&[0xef, 0xbb, 0xbf])
+        .unwrap_or(&content);
+    String::from_utf8(content.to_vec()).map_err(|e| e.to_string())
+}
+
+/// Unescape like FString::ReplaceEscapedCharWithChar of UE and C strings of PO
+fn unescape_string(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => ret.push('\n'),
+            Some('r') => ret.push('\r'),
+            Some('t') => ret.push('\t'),
+            Some(x) => ret.push(x),
+            None => ret.push('\\'),
+        }
+    }
+    ret
+}
+
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut ret = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    cell.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                cell.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                ret.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(c),
+        }
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        ret.push(row);
+    }
+    ret
+}
+
+impl LocalizeTranslations {
+    fn insert(&mut self, field_name: &str, source: String, translation: String) {
+        if source.is_empty() || translation.is_empty() {
+            return;
+        }
+
+        if field_name.is_empty() {
+            self.by_value.insert(source, translation);
+        } else {
+            self.by_field
+                .insert((field_name.to_string(), source), translation);
+        }
+    }
+
+    fn get(&self, field_name: &str, source: &str) -> Option<&String> {
+        self.by_field
+            .get(&(field_name.to_string(), source.to_string()))
+            .or_else(|| self.by_value.get(source))
+    }
+
+    fn load_json_entries(&mut self, entries: &json::JsonValue) {
+        for (key, value) in entries.entries() {
+            if let Some(x) = value.as_str() {
+                self.insert("", key.to_string(), x.to_string());
+            } else if let Some(x) = value["translation"].as_str() {
+                self.insert("", key.to_string(), x.to_string());
+            }
+        }
+    }
+
+    /// String table json generated by --output-string-table-json with `translation` added to each string,
+    /// or an object of source strings and translations
+    fn load_json(&mut self, content: &str) -> Result<(), String> {
+        let document = json::parse(content).map_err(|e| e.to_string())?;
+        let blocks: Vec<&json::JsonValue> = if document.is_array() {
+            document.members().collect()
+        } else if document.has_key("body") {
+            vec![&document]
+        } else {
+            self.load_json_entries(&document);
+            return Ok(());
+        };
+
+        for block in blocks {
+            let body = &block["body"];
+            if body.is_array() {
+                for item in body.members() {
+                    self.load_json_entries(item);
+                }
+            } else {
+                self.load_json_entries(body);
+            }
+        }
+        Ok(())
+    }
+
+    /// Csv with header, columns of source string and translation are required, and field full name is optional
+    fn load_csv(&mut self, content: &str) -> Result<(), String> {
+        let rows = parse_csv(content);
+        let header = match rows.first() {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let find_column = |names: &[&str]| {
+            header
+                .iter()
+                .position(|x| names.iter().any(|name| x.trim().eq_ignore_ascii_case(name)))
+        };
+
+        let source_column = find_column(&["SourceString", "source"])
+            .ok_or_else(|| "there is no SourceString or source column".to_string())?;
+        let translation_column = find_column(&["translation", "target", "LocalizedString"])
+            .ok_or_else(|| {
+                "there is no translation, target or LocalizedString column".to_string()
+            })?;
+        let field_column = find_column(&["field", "msgctxt", "context"]);
+        // Strings of UE StringTable are escaped
+        let is_ue_string_table = header[source_column].trim() == "SourceString";
+
+        for row in rows.iter().skip(1) {
+            let get_cell = |column: usize| {
+                let cell = row.get(column).map(|x| x.as_str()).unwrap_or_default();
+                if is_ue_string_table {
+                    unescape_string(cell)
+                } else {
+                    cell.to_string()
+                }
+            };
+            let field_name = field_column.map(get_cell).unwrap_or_default();
+            self.insert(
+                &field_name,
+                get_cell(source_column),
+                get_cell(translation_column),
+            );
+        }
+        Ok(())
+    }
+
+    /// PO file of gettext, msgctxt is the full name of field like --output-string-table-po, fuzzy entries are ignored
+    fn load_po(&mut self, content: &str) -> Result<(), String> {
+        #[derive(Default)]
+        struct PoEntry {
+            msgctxt: String,
+            msgid: String,
+            msgstr: String,
+            is_fuzzy: bool,
+        }
+
+        let mut entries: Vec<PoEntry> = Vec::new();
+        // Flags in comments before the entry
+        let mut is_fuzzy = false;
+        // 0: msgctxt, 1: msgid, 2: msgstr, others: ignored
+        let mut current = usize::MAX;
+        for (line_index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('#') {
+                if line.starts_with("#,") && line.contains("fuzzy") {
+                    is_fuzzy = true;
+                }
+                continue;
+            }
+
+            let (keyword, value) = match line.split_once(char::is_whitespace) {
+                Some((k, v)) if !line.starts_with('"') => (k, v.trim()),
+                _ => ("", line),
+            };
+            if !value.starts_with('"') || !value.ends_with('"') || value.len() < 2 {
+                return Err(format!("line {}, invalid string {}", line_index + 1, value));
+            }
+            let value = unescape_string(&value[1..value.len() - 1]);
+
+            match keyword {
+                "" => {}
+                "msgctxt" | "msgid" => {
+                    // msgid after msgctxt belongs to the same entry
+                    if keyword == "msgctxt" || current != 0 {
+                        entries.push(PoEntry {
+                            is_fuzzy,
+                            ..Default::default()
+                        });
+                        is_fuzzy = false;
+                    }
+                    current = if keyword == "msgctxt" { 0 } else { 1 };
+                }
+                "msgstr" | "msgstr[0]" => current = 2,
+                _ => current = usize::MAX,
+            }
+
+            if let Some(entry) = entries.last_mut() {
+                match current {
+                    0 => entry.msgctxt.push_str(&value),
+                    1 => entry.msgid.push_str(&value),
+                    2 => entry.msgstr.push_str(&value),
+                    _ => {}
+                }
+            }
+        }
+
+        for entry in entries {
+            if !entry.is_fuzzy {
+                self.insert(&entry.msgctxt, entry.msgid, entry.msgstr);
+            }
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.by_field.len() + self.by_value.len()
+    }
+}
+
+fn translate_string(
+    field_name: &str,
+    value: &str,
+    filter: &StringTableFilter,
+    translate: &mut dyn FnMut(&str, &str) -> Option<String>,
+) -> Option<String> {
+    if !filter.filter_value(value) {
+        return None;
+    }
+
+    translate(field_name, value)
+}
+
+/// Replace strings in message with the same rules of string table plugin, keys of maps are not changed
+///
+/// `translate` receives the field full name and the source string, and returns the replacement
+fn localize_message(
+    message: &mut dyn MessageDyn,
+    filter: &StringTableFilter,
+    translate: &mut dyn FnMut(&str, &str) -> Option<String>,
+) {
+    let message_desc = message.descriptor_dyn();
+    if !filter.filter_message(&message_desc) {
+        return;
+    }
+
+    for field in message_desc.fields() {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(_)) => {
+                if field.has_field(message) {
+                    localize_message(field.mut_message(message), filter, translate);
+                }
+            }
+            RuntimeFieldType::Singular(RuntimeType::String) => {
+                if !filter.filter_field(&field) {
+                    continue;
+                }
+
+                let translation = match field.get_singular(message) {
+                    Some(ReflectValueRef::String(s)) => {
+                        translate_string(&field.full_name(), s, filter, translate)
+                    }
+                    _ => None,
+                };
+                if let Some(x) = translation {
+                    field.set_singular_field(message, ReflectValueBox::String(x));
+                }
+            }
+            RuntimeFieldType::Singular(_) => {}
+            RuntimeFieldType::Repeated(_) => {
+                if !filter.filter_field(&field) {
+                    continue;
+                }
+
+                let mut repeated = field.mut_repeated(message);
+                for index in 0..repeated.len() {
+                    match repeated.get(index).to_box() {
+                        ReflectValueBox::Message(mut m) => {
+                            localize_message(m.as_mut(), filter, translate);
+                            repeated.set(index, ReflectValueBox::Message(m));
+                        }
+                        ReflectValueBox::String(s) => {
+                            if let Some(x) =
+                                translate_string(&field.full_name(), &s, filter, translate)
+                            {
+                                repeated.set(index, ReflectValueBox::String(x));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            RuntimeFieldType::Map(_, _) => {
+                let entries: Vec<(ReflectValueBox, ReflectValueBox)> = field
+                    .get_map(message)
+                    .into_iter()
+                    .map(|(k, v)| (k.to_box(), v.to_box()))
+                    .collect();
+                let mut map = field.mut_map(message);
+                for (k, v) in entries {
+                    match v {
+                        ReflectValueBox::Message(mut m) => {
+                            localize_message(m.as_mut(), filter, translate);
+                            map.insert(k, ReflectValueBox::Message(m));
+                        }
+                        ReflectValueBox::String(s) => {
+                            if !filter.filter_field(&field) {
+                                continue;
+                            }
+
+                            if let Some(x) =
+                                translate_string(&field.full_name(), &s, filter, translate)
+                            {
+                                map.insert(k, ReflectValueBox::String(x));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replace strings of all rows and write them with new hash_code, return path of the output file
+fn localize_bin_file(
+    args: &DumpOptions,
+    bin_file: &str,
+    output: &str,
+    desc_index: &mut FileDescriptorIndex,
+    filter: &StringTableFilter,
+    translate: &mut dyn FnMut(&str, &str) -> Option<String>,
+) -> Result<String, ()> {
+    let mut bin = XresloaderBinFile::load(bin_file, desc_index)?;
+    for (row_index, row) in bin.rows.iter_mut().enumerate() {
+        localize_message(row.as_mut(), filter, translate);
+        match row.write_to_bytes_dyn() {
+            Ok(x) => bin.data_blocks.data_block[row_index] = x,
+            Err(e) => {
+                error!(
+                    "Encode row {} of {} after localization failed, {}",
+                    row_index + 1,
+                    bin_file,
+                    e
+                );
+                return Err(());
+            }
+        }
+    }
+
+    let algorithm = if !args.localize_hash_algorithm.is_empty() {
+        args.localize_hash_algorithm.clone()
+    } else {
+        match bin.data_blocks.header.hash_code.split_once(':') {
+            Some((x, _)) if !x.trim().is_empty() => x.trim().to_string(),
+            _ => "sha256".to_string(),
+        }
+    };
+    let header = bin.data_blocks.header.mut_or_insert_default();
+    header.hash_code = match hash_code::calculate_hash_code(&algorithm, &bin.data_blocks.data_block)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Calculate hash code of {} failed, {}", bin_file, e);
+            return Err(());
+        }
+    };
+    if !args.localize_data_ver.is_empty() {
+        header.data_ver = args.localize_data_ver.clone();
+    }
+
+    let output_file = utility::get_output_file_path(output, bin_file, "bin");
+    xresloader_bin::save_data_blocks(&output_file, &bin.data_blocks)?;
+    Ok(output_file)
+}
+
+/// Replace strings in binary files by translations and write localized binary files, return true if there is any error
+pub fn run(args: &DumpOptions, desc_index: &mut FileDescriptorIndex) -> bool {
+    if args.localize_input.is_empty() || args.localize_output.is_empty() {
+        error!("Both --localize-input and --localize-output are required to run localize mode");
+        return true;
+    }
+
+    if args.localize_bin.is_empty() {
+        error!("There is no binary file to localize, please add them by --localize-bin");
+        return true;
+    }
+
+    if !utility::check_output_path(
+        &args.localize_output,
+        "--localize-output",
+        args.localize_bin.len(),
+    ) {
+        return true;
+    }
+
+    let format = match get_input_format(args) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("{}", e);
+            return true;
+        }
+    };
+
+    let content = match read_translation_file(&args.localize_input) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Try to read file {} failed, {}", &args.localize_input, e);
+            return true;
+        }
+    };
+
+    let mut translations = LocalizeTranslations::default();
+    let load_result = match format {
+        LocalizeInputFormat::Json => translations.load_json(&content),
+        LocalizeInputFormat::Csv => translations.load_csv(&content),
+        LocalizeInputFormat::Po => translations.load_po(&content),
+    };
+    if let Err(e) = load_result {
+        error!(
+            "Load translations from {} failed, {}",
+            &args.localize_input, e
+        );
+        return true;
+    }
+    if translations.len() == 0 {
+        warn!("There is no translation in {}", &args.localize_input);
+    }
+
+    let (filter, has_filter_error) = string_table::build_string_table_filter(args);
+    if has_filter_error {
+        return true;
+    }
+
+    let mut has_error = false;
+    for bin_file in &args.localize_bin {
+        let mut stats = LocalizeStats::default();
+        let mut translate =
+            |field_name: &str, value: &str| match translations.get(field_name, value) {
+                Some(x) => {
+                    stats.replaced_count += 1;
+                    Some(x.clone())
+                }
+                None => {
+                    stats.missing.insert(value.to_string());
+                    None
+                }
+            };
+        let output_file = match localize_bin_file(
+            args,
+            bin_file,
+            &args.localize_output,
+            desc_index,
+            &filter,
+            &mut translate,
+        ) {
+            Ok(x) => x,
+            Err(_) => {
+                has_error = true;
+                continue;
+            }
+        };
+
+        if !stats.missing.is_empty() {
+            warn!(
+                "There are {} string(s) without translation in {}",
+                stats.missing.len(),
+                bin_file
+            );
+            let mut missing: Vec<&String> = stats.missing.iter().collect();
+            missing.sort();
+            for value in missing {
+                debug!("Missing translation: {}", value);
+            }
+        }
+
+        if !args.silence {
+            info!(
+                "Localize {} string(s) of {} into {} success",
+                stats.replaced_count, bin_file, output_file
+            );
+        }
+    }
+    has_error
+}
+
+/// Placeholders and markups which are kept intact by pseudo-localization
+const PSEUDO_LOCALIZE_PLACEHOLDER_PATTERN: &str = concat!(
+    // {0}, {name}, {0:N2}
+    r"\{[^{}]*\}",
+    // %d, %s, %1$s, %-5.2f, %lld, %@ and %%
+    r"|%%|%(\d+\$)?[-+0#]*(\d+|\*)?(\.(\d+|\*))?(hh|h|ll|l|L|q|j|z|t)?[diouxXeEfFgGaAcCsSpn@]",
+    // Rich text tags, like <Tag>, </>, <color=#ff0000>
+    r"|<[^<>]*>",
+);
+
+/// Padding characters appended for length expansion
+const PSEUDO_LOCALIZE_PADDING: char = '~';
+
+struct PseudoLocalizer {
+    prefix: String,
+    suffix: String,
+    /// Percent of extra characters, based on characters out of placeholders
+    expansion: u32,
+    with_accents: bool,
+    placeholder: regex::Regex,
+}
+
+fn get_accented_char(c: char) -> char {
+    match c {
+        'A' => 'Å',
+        'B' => 'Ɓ',
+        'C' => 'Ç',
+        'D' => 'Ð',
+        'E' => 'É',
+        'F' => 'Ƒ',
+        'G' => 'Ĝ',
+        'H' => 'Ĥ',
+        'I' => 'Î',
+        'J' => 'Ĵ',
+        'K' => 'Ķ',
+        'L' => 'Ļ',
+        'M' => 'Ṁ',
+        'N' => 'Ñ',
+        'O' => 'Ö',
+        'P' => 'Þ',
+        'Q' => 'Ǫ',
+        'R' => 'Ŕ',
+        'S' => 'Š',
+        'T' => 'Ţ',
+        'U' => 'Û',
+        'V' => 'Ṽ',
+        'W' => 'Ŵ',
+        'X' => 'Ẋ',
+        'Y' => 'Ý',
+        'Z' => 'Ž',
+        'a' => 'å',
+        'b' => 'ƀ',
+        'c' => 'ç',
+        'd' => 'ð',
+        'e' => 'é',
+        'f' => 'ƒ',
+        'g' => 'ĝ',
+        'h' => 'ĥ',
+        'i' => 'î',
+        'j' => 'ĵ',
+        'k' => 'ķ',
+        'l' => 'ļ',
+        'm' => 'ṁ',
+        'n' => 'ñ',
+        'o' => 'ö',
+        'p' => 'þ',
+        'q' => 'ǫ',
+        'r' => 'ŕ',
+        's' => 'š',
+        't' => 'ţ',
+        'u' => 'û',
+        'v' => 'ṽ',
+        'w' => 'ŵ',
+        'x' => 'ẋ',
+        'y' => 'ý',
+        'z' => 'ž',
+        _ => c,
+    }
+}
+
+impl PseudoLocalizer {
+    fn build(args: &DumpOptions) -> Result<PseudoLocalizer, String> {
+        Ok(PseudoLocalizer {
+            prefix: args.pseudo_localize_prefix.clone(),
+            suffix: args.pseudo_localize_suffix.clone(),
+            expansion: args.pseudo_localize_expansion,
+            with_accents: !args.pseudo_localize_without_accents,
+            placeholder: regex::Regex::new(PSEUDO_LOCALIZE_PLACEHOLDER_PATTERN)
+                .map_err(|e| e.to_string())?,
+        })
+    }
+
+    /// Append text out of placeholders, return count of characters
+    fn push_text(&self, output: &mut String, text: &str) -> usize {
+        if self.with_accents {
+            output.extend(text.chars().map(get_accented_char));
+        } else {
+            output.push_str(text);
+        }
+        text.chars().count()
+    }
+
+    fn convert(&self, value: &str) -> String {
+        let mut ret =
+            String::with_capacity(value.len() * 2 + self.prefix.len() + self.suffix.len());
+        ret.push_str(&self.prefix);
+
+        let mut text_length = 0;
+        let mut last_end = 0;
+        for placeholder in self.placeholder.find_iter(value) {
+            text_length += self.push_text(&mut ret, &value[last_end..placeholder.start()]);
+            ret.push_str(placeholder.as_str());
+            last_end = placeholder.end();
+        }
+        text_length += self.push_text(&mut ret, &value[last_end..]);
+
+        let padding = (text_length * self.expansion as usize).div_ceil(100);
+        ret.extend(std::iter::repeat_n(PSEUDO_LOCALIZE_PADDING, padding));
+        ret.push_str(&self.suffix);
+        ret
+    }
+}
+
+/// Write pseudo-localized copies of binary files, return true if there is any error
+pub fn run_pseudo_localize(args: &DumpOptions, desc_index: &mut FileDescriptorIndex) -> bool {
+    if args.localize_bin.is_empty() {
+        error!("There is no binary file to pseudo-localize, please add them by --localize-bin");
+        return true;
+    }
+
+    if !utility::check_output_path(
+        &args.pseudo_localize_output,
+        "--pseudo-localize-output",
+        args.localize_bin.len(),
+    ) {
+        return true;
+    }
+
+    let pseudo_localizer = match PseudoLocalizer::build(args) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Build pseudo-localization rules failed, {}", e);
+            return true;
+        }
+    };
+
+    let (filter, has_filter_error) = string_table::build_string_table_filter(args);
+    if has_filter_error {
+        return true;
+    }
+
+    let mut has_error = false;
+    for bin_file in &args.localize_bin {
+        let mut replaced_count = 0;
+        let mut translate = |_: &str, value: &str| {
+            if value.is_empty() {
+                return None;
+            }
+            replaced_count += 1;
+            Some(pseudo_localizer.convert(value))
+        };
+        match localize_bin_file(
+            args,
+            bin_file,
+            &args.pseudo_localize_output,
+            desc_index,
+            &filter,
+            &mut translate,
+        ) {
+            Ok(output_file) => {
+                if !args.silence {
+                    info!(
+                        "Pseudo-localize {} string(s) of {} into {} success",
+                        replaced_count, bin_file, output_file
+                    );
+                }
+            }
+            Err(_) => has_error = true,
+        }
+    }
+    has_error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_po(content: &str) -> LocalizeTranslations {
+        let mut ret = LocalizeTranslations::default();
+        ret.load_po(content).unwrap();
+        ret
+    }
+
+    #[test]
+    fn unescape_ue_and_c_strings() {
+        assert_eq!(unescape_string(r"a\nb\tc\r"), "a\nb\tc\r");
+        assert_eq!(unescape_string(r#"say \"hi\""#), "say \"hi\"");
+        assert_eq!(unescape_string(r"a\\n"), "a\\n");
+        assert_eq!(unescape_string(r"end\"), "end\\");
+    }
+
+    #[test]
+    fn parse_csv_quoted_cells() {
+        let rows =
+            parse_csv("Key,SourceString\r\n\"a,b\",\"say \"\"hi\"\"\"\r\n\"multi\nline\",\"\"\n");
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Key".to_string(), "SourceString".to_string()],
+                vec!["a,b".to_string(), "say \"hi\"".to_string()],
+                vec!["multi\nline".to_string(), "".to_string()],
+            ]
+        );
+
+        // The last line may not end with a line break
+        assert_eq!(
+            parse_csv("a,b\nc,\"d\"\"\""),
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["c".to_string(), "d\"".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn load_csv_of_ue_string_table() {
+        let mut translations = LocalizeTranslations::default();
+        translations
+            .load_csv("Key,SourceString,translation\n1,\"Line1\\nLine2\",\"Ligne1\\nLigne2\"\n2,\"say \"\"hi\"\"\",\"dis \"\"salut\"\"\"\n")
+            .unwrap();
+        assert_eq!(
+            translations.get("", "Line1\nLine2"),
+            Some(&"Ligne1\nLigne2".to_string())
+        );
+        assert_eq!(
+            translations.get("", "say \"hi\""),
+            Some(&"dis \"salut\"".to_string())
+        );
+    }
+
+    #[test]
+    fn load_po_entries_with_msgctxt() {
+        let translations = load_po(
+            r#"
+msgctxt "demo.ItemCfg.name"
+msgid "Sword"
+msgstr "Epee"
+
+msgctxt "demo.ItemCfg.desc"
+msgid "Sword"
+msgstr "Une epee"
+
+msgid "Shield"
+msgstr "Bouclier"
+"#,
+        );
+        assert_eq!(translations.len(), 3);
+        assert_eq!(
+            translations.get("demo.ItemCfg.name", "Sword"),
+            Some(&"Epee".to_string())
+        );
+        assert_eq!(
+            translations.get("demo.ItemCfg.desc", "Sword"),
+            Some(&"Une epee".to_string())
+        );
+        // Entries without msgctxt are used by all fields
+        assert_eq!(
+            translations.get("demo.ItemCfg.name", "Shield"),
+            Some(&"Bouclier".to_string())
+        );
+        assert_eq!(translations.get("demo.ItemCfg.other", "Sword"), None);
+    }
+
+    #[test]
+    fn load_po_multi_line_strings() {
+        let translations = load_po(
+            r#"
+msgid ""
+"Line1\n"
+"Line2 \"quoted\""
+msgstr ""
+"Ligne1\n"
+"Ligne2"
+"#,
+        );
+        assert_eq!(
+            translations.get("", "Line1\nLine2 \"quoted\""),
+            Some(&"Ligne1\nLigne2".to_string())
+        );
+    }
+
+    #[test]
+    fn load_po_header_fuzzy_and_plural() {
+        let translations = load_po(
+            r#"
+# Header entry has an empty msgid
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+#: demo.ItemCfg.name
+#, fuzzy, c-format
+#| msgid "Old sword"
+msgid "Sword"
+msgstr "Epee"
+
+msgid "Apple"
+msgid_plural "Apples"
+msgstr[0] "Pomme"
+msgstr[1] "Pommes"
+
+#~ msgid "Obsolete"
+#~ msgstr "Obsolete"
+
+msgid "Untranslated"
+msgstr ""
+"#,
+        );
+        assert_eq!(translations.len(), 1);
+        assert_eq!(translations.get("", ""), None);
+        assert_eq!(translations.get("", "Sword"), None);
+        assert_eq!(translations.get("", "Apple"), Some(&"Pomme".to_string()));
+        assert_eq!(translations.get("", "Apples"), None);
+        assert_eq!(translations.get("", "Obsolete"), None);
+        assert_eq!(translations.get("", "Untranslated"), None);
+    }
+
+    #[test]
+    fn load_po_invalid_string() {
+        let mut translations = LocalizeTranslations::default();
+        let err = translations
+            .load_po("msgid \"Sword\nmsgstr \"Epee\"\n")
+            .err()
+            .unwrap();
+        assert!(err.starts_with("line 1,"), "{}", err);
+    }
+
+    fn pseudo_localizer(expansion: u32, with_accents: bool) -> PseudoLocalizer {
+        PseudoLocalizer {
+            prefix: "[".to_string(),
+            suffix: "]".to_string(),
+            expansion,
+            with_accents,
+            placeholder: regex::Regex::new(PSEUDO_LOCALIZE_PLACEHOLDER_PATTERN).unwrap(),
+        }
+    }
+
+    #[test]
+    fn pseudo_localize_keeps_placeholders() {
+        let localizer = pseudo_localizer(0, true);
+        assert_eq!(localizer.convert("Hello {0}!"), "[Ĥéļļö {0}!]");
+        assert_eq!(localizer.convert("{name} x{0:N2}"), "[{name} ẋ{0:N2}]");
+        assert_eq!(
+            localizer.convert("%1$s has %d%% off"),
+            "[%1$s ĥåš %d%% öƒƒ]"
+        );
+        assert_eq!(localizer.convert("%-5.2f%lld"), "[%-5.2f%lld]");
+        assert_eq!(
+            localizer.convert("<color=#f00>Red</> text"),
+            "[<color=#f00>Ŕéð</> ţéẋţ]"
+        );
+        assert_eq!(localizer.convert(""), "[]");
+    }
+
+    #[test]
+    fn pseudo_localize_expansion() {
+        let localizer = pseudo_localizer(50, false);
+        assert_eq!(localizer.convert("abcd"), "[abcd~~]");
+        assert_eq!(localizer.convert("abc"), "[abc~~]");
+        // Placeholders are not counted
+        assert_eq!(localizer.convert("{0}ab<b>%s</>"), "[{0}ab<b>%s</>~]");
+        assert_eq!(pseudo_localizer(0, false).convert("Hello"), "[Hello]");
+    }
+}
@@ -0,0 +1,304 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_flatten::{self, FlattenTable};
+use super::row_projection::RowProjection;
+use super::utility;
+
+use protobuf::MessageDyn;
+
+struct CsvTableContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    pub table: FlattenTable,
+    pub row_index: Vec<usize>,
+    pub data_source: Vec<Rc<dump_plugin::DumpPluginItemDataSource>>,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for CsvTableContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Escape a cell, CSV cells are quoted when necessary and TSV cells use backslash escapes
+pub fn escape_csv_cell(input: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        let mut ret = String::with_capacity(input.len());
+        for c in input.chars() {
+            match c {
+                '\\' => ret.push_str("\\\\"),
+                '\t' => ret.push_str("\\t"),
+                '\r' => ret.push_str("\\r"),
+                '\n' => ret.push_str("\\n"),
+                _ => ret.push(c),
+            }
+        }
+        return ret;
+    }
+
+    if input.contains(delimiter) || input.contains(['"', '\r', '\n']) {
+        format!("\"{}\"", input.replace('"', "\"\""))
+    } else {
+        input.to_string()
+    }
+}
+
+impl CsvTableContent {
+    fn header_cells(&self, with_row_index: bool, with_source: bool) -> Vec<String> {
+        let mut ret = Vec::with_capacity(self.table.column_count() + 3);
+        if with_row_index {
+            ret.push("_row".to_string());
+        }
+        if with_source {
+            ret.push("_file".to_string());
+            ret.push("_sheet".to_string());
+        }
+        for (_, column) in self.table.columns() {
+            ret.push(column.path.clone());
+        }
+        ret
+    }
+
+    fn row_cells(&self, row: usize, with_row_index: bool, with_source: bool) -> Vec<String> {
+        let mut ret = Vec::with_capacity(self.table.column_count() + 3);
+        if with_row_index {
+            ret.push(self.row_index[row].to_string());
+        }
+        if with_source {
+            ret.push(self.data_source[row].file.clone());
+            ret.push(self.data_source[row].sheet.clone());
+        }
+        for (key, _) in self.table.columns() {
+            ret.push(match self.table.get(row, key) {
+                Some(x) => row_flatten::flatten_value_to_string(x),
+                None => String::new(),
+            });
+        }
+        ret
+    }
+
+    pub fn to_lines(
+        &self,
+        delimiter: char,
+        with_row_index: bool,
+        with_source: bool,
+    ) -> Vec<String> {
+        let join_cells = |cells: Vec<String>| {
+            cells
+                .iter()
+                .map(|x| escape_csv_cell(x, delimiter))
+                .collect::<Vec<String>>()
+                .join(&delimiter.to_string())
+        };
+
+        let mut ret = Vec::with_capacity(self.table.row_count() + 1);
+        ret.push(join_cells(self.header_cells(with_row_index, with_source)));
+        for row in 0..self.table.row_count() {
+            ret.push(join_cells(self.row_cells(row, with_row_index, with_source)));
+        }
+        ret
+    }
+
+    pub fn to_json(
+        &self,
+        json_item_head: json::JsonValue,
+        with_row_index: bool,
+        with_source: bool,
+    ) -> json::JsonValue {
+        let mut json_item = json::JsonValue::new_object();
+        let _ = json_item.insert("head", json_item_head);
+
+        let header = self.header_cells(with_row_index, with_source);
+        let mut json_item_body = json::JsonValue::new_array();
+        for row in 0..self.table.row_count() {
+            let mut json_row = json::JsonValue::new_object();
+            for (name, value) in header
+                .iter()
+                .zip(self.row_cells(row, with_row_index, with_source))
+            {
+                let _ = json_row.insert(name, value);
+            }
+            let _ = json_item_body.push(json_row);
+        }
+        let _ = json_item.insert("body", json_item_body);
+
+        json_item
+    }
+}
+
+pub struct DumpPluginCsvTable {
+    content: VecDeque<Box<CsvTableContent>>,
+    /// Rows are received without projection and flattened by --select, so indexes of repeated fields are kept
+    projection: Option<RowProjection>,
+
+    // output
+    delimiter: char,
+    extension: &'static str,
+    with_row_index: bool,
+    with_source: bool,
+    write_to_path: String,
+}
+
+impl DumpPluginCsvTable {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_csv.is_empty() {
+            return (None, false);
+        }
+
+        let (delimiter, extension) = match args.csv_format.to_lowercase().as_str() {
+            "csv" => (',', "csv"),
+            "tsv" => ('\t', "tsv"),
+            _ => {
+                error!(
+                    "Invalid --csv-format {}, only csv and tsv are supported",
+                    args.csv_format
+                );
+                return (None, true);
+            }
+        };
+
+        if !utility::check_output_path(&args.output_csv, "--output-csv", args.bin_file.len()) {
+            return (None, true);
+        }
+
+        let projection = match RowProjection::build(&args.select) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Parse --select failed, {}", e);
+                return (None, true);
+            }
+        };
+
+        (
+            Some(Box::new(DumpPluginCsvTable {
+                content: VecDeque::new(),
+                projection,
+                delimiter,
+                extension,
+                with_row_index: args.csv_with_row_index,
+                with_source: args.csv_with_source,
+                write_to_path: args.output_csv.clone(),
+            })),
+            false,
+        )
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginCsvTable {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(CsvTableContent {
+            head: data_source,
+            table: FlattenTable::new(),
+            row_index: Vec::new(),
+            data_source: Vec::new(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<CsvTableContent>() {
+            rb.table.add_row(message, self.projection.as_ref());
+            rb.row_index.push(row_index);
+            rb.data_source.push(data_source.into());
+        } else {
+            error!(
+                "In DumpPluginCsvTable::load_message, the block is not CsvTableContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(rb) = block.into_any().downcast::<CsvTableContent>() {
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginCsvTable::push_block, the block is not CsvTableContent, ignore this message"
+            );
+        }
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        let mut ret = Vec::with_capacity(self.content.len());
+        for csv_table in &self.content {
+            ret.push(csv_table.to_json(
+                self.header_to_json(csv_table.head.as_ref()),
+                self.with_row_index,
+                self.with_source,
+            ));
+        }
+        ret
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        for csv_table in &self.content {
+            ret.extend(csv_table.to_lines(self.delimiter, self.with_row_index, self.with_source));
+        }
+        ret
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = Ok(());
+        let line_ending: &[u8] = if self.delimiter == '\t' {
+            b"\n"
+        } else {
+            b"\r\n"
+        };
+        for csv_table in &self.content {
+            let output_file = utility::get_output_file_path(
+                &self.write_to_path,
+                &csv_table.head.file_path,
+                self.extension,
+            );
+            match utility::create_output_file(&output_file) {
+                Ok(mut f) => {
+                    for line in
+                        csv_table.to_lines(self.delimiter, self.with_row_index, self.with_source)
+                    {
+                        if let Err(e) = f
+                            .write_all(line.as_bytes())
+                            .and_then(|_| f.write_all(line_ending))
+                        {
+                            error!("Try to write csv table to {} failed, {}", output_file, e);
+                            ret = Err(());
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Try to open {} to write csv table failed, {}",
+                        output_file, e
+                    );
+                    ret = Err(());
+                }
+            }
+        }
+
+        ret
+    }
+}
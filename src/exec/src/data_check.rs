@@ -0,0 +1,343 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_flatten;
+use super::utility;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{ReflectValueRef, RuntimeFieldType};
+
+pub struct DataCheckRow {
+    pub row_index: usize,
+    pub data_source: Rc<dump_plugin::DumpPluginItemDataSource>,
+}
+
+pub struct DataCheckViolation {
+    pub rule: &'static str,
+    pub row_index: usize,
+    pub data_source: Rc<dump_plugin::DumpPluginItemDataSource>,
+    pub field_path: String,
+    pub value: String,
+    pub message: String,
+}
+
+pub struct DataCheckContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    pub violations: Vec<DataCheckViolation>,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for DataCheckContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl DataCheckViolation {
+    pub fn to_text(&self, file_path: &str) -> String {
+        let field = if self.value.is_empty() {
+            self.field_path.clone()
+        } else {
+            format!("{}={}", self.field_path, self.value)
+        };
+        format!(
+            "{}: row {}({}|{}), [{}] {}, {}",
+            file_path,
+            self.row_index,
+            self.data_source.file,
+            self.data_source.sheet,
+            self.rule,
+            field,
+            self.message
+        )
+    }
+
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut ret = json::JsonValue::new_object();
+        let _ = ret.insert("rule", self.rule);
+        let _ = ret.insert("row", self.row_index);
+        let _ = ret.insert("file", self.data_source.file.clone());
+        let _ = ret.insert("sheet", self.data_source.sheet.clone());
+        let _ = ret.insert("field_path", self.field_path.clone());
+        let _ = ret.insert("value", self.value.clone());
+        let _ = ret.insert("message", self.message.clone());
+        ret
+    }
+}
+
+impl DataCheckContent {
+    pub fn add_violation(
+        &mut self,
+        rule: &'static str,
+        row: &DataCheckRow,
+        field_path: String,
+        value: String,
+        message: String,
+    ) {
+        let violation = DataCheckViolation {
+            rule,
+            row_index: row.row_index,
+            data_source: row.data_source.clone(),
+            field_path,
+            value,
+            message,
+        };
+        error!("{}", violation.to_text(&self.head.file_path));
+        self.violations.push(violation);
+    }
+
+    pub fn to_json(&self, json_item_head: json::JsonValue) -> json::JsonValue {
+        let mut json_item = json::JsonValue::new_object();
+        let _ = json_item.insert("head", json_item_head);
+
+        let mut json_item_body = json::JsonValue::new_array();
+        for violation in &self.violations {
+            let _ = json_item_body.push(violation.to_json());
+        }
+        let _ = json_item.insert("body", json_item_body);
+
+        json_item
+    }
+
+    pub fn to_text(&self) -> Vec<String> {
+        self.violations
+            .iter()
+            .map(|violation| violation.to_text(&self.head.file_path))
+            .collect()
+    }
+}
+
+pub trait DataCheckRule {
+    fn check_message(
+        &mut self,
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        row: &DataCheckRow,
+    );
+
+    /// Called after all rows of a binary file are checked
+    fn finish_block(&mut self, _block: &mut DataCheckContent) {}
+
+    /// Errors of rule itself, such as bad validator expressions
+    fn has_error(&self) -> bool {
+        false
+    }
+}
+
+pub fn join_field_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+pub fn map_key_to_path_index(key: &ReflectValueRef) -> String {
+    match key {
+        ReflectValueRef::String(s) => format!("\"{}\"", s),
+        _ => utility::pb_value_to_string(key),
+    }
+}
+
+/// Visit the message and all nested messages in singular, repeated and map fields with their field paths
+pub fn for_each_nested_message<F>(message: &dyn MessageDyn, path: &str, func: &mut F)
+where
+    F: FnMut(&dyn MessageDyn, &str),
+{
+    func(message, path);
+
+    for field in message.descriptor_dyn().fields() {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(_) => {
+                if let Some(ReflectValueRef::Message(m)) = field.get_singular(message) {
+                    for_each_nested_message(m.deref(), &join_field_path(path, field.name()), func);
+                }
+            }
+            RuntimeFieldType::Repeated(_) => {
+                let field_path = join_field_path(path, field.name());
+                for (index, v) in field.get_repeated(message).into_iter().enumerate() {
+                    if let ReflectValueRef::Message(m) = v {
+                        for_each_nested_message(
+                            m.deref(),
+                            &format!("{}[{}]", field_path, index),
+                            func,
+                        );
+                    }
+                }
+            }
+            RuntimeFieldType::Map(_, _) => {
+                let field_path = join_field_path(path, field.name());
+                // Keep stable order of map, so reports can be compared
+                let map = field.get_map(message);
+                let mut entries: Vec<(ReflectValueRef, ReflectValueRef)> =
+                    (&map).into_iter().collect();
+                entries.sort_by_key(|(k, _)| row_flatten::map_key_to_flatten_key(k));
+                for (k, v) in entries {
+                    if let ReflectValueRef::Message(m) = v {
+                        for_each_nested_message(
+                            m.deref(),
+                            &format!("{}[{}]", field_path, map_key_to_path_index(&k)),
+                            func,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+type DataCheckRuleBuildFn =
+    fn(&DumpOptions, &FileDescriptorIndex) -> (Option<Box<dyn DataCheckRule>>, bool);
+
+pub struct DumpPluginDataCheck {
+    rules: Vec<Box<dyn DataCheckRule>>,
+    content: VecDeque<Box<DataCheckContent>>,
+
+    // output
+    output_pretty: bool,
+    write_to_text_file: String,
+    write_to_json_file: String,
+}
+
+impl DumpPluginDataCheck {
+    pub fn build(
+        args: &DumpOptions,
+        desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        let mut rules = Vec::new();
+        let mut has_error = false;
+
+        let new_rule_fns: [DataCheckRuleBuildFn; 4] = [
+            super::data_check_validator::DataCheckValidator::build,
+            super::data_check_unique_tag::DataCheckUniqueTag::build,
+            super::data_check_required::DataCheckRequired::build,
+            super::data_check_list_size::DataCheckListSize::build,
+        ];
+        for new_rule_fn in &new_rule_fns {
+            let (new_rule_inst, new_rule_has_error) = new_rule_fn(args, desc_index);
+            if let Some(r) = new_rule_inst {
+                rules.push(r);
+            }
+            has_error |= new_rule_has_error;
+        }
+
+        if rules.is_empty() || has_error {
+            return (None, has_error);
+        }
+
+        (
+            Some(Box::new(DumpPluginDataCheck {
+                rules,
+                content: VecDeque::new(),
+                output_pretty: args.pretty || args.check_report_pretty,
+                write_to_text_file: args.output_check_report_text.clone(),
+                write_to_json_file: args.output_check_report_json.clone(),
+            })),
+            false,
+        )
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginDataCheck {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(DataCheckContent {
+            head: data_source,
+            violations: Vec::new(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<DataCheckContent>() {
+            let row = DataCheckRow {
+                row_index,
+                data_source: data_source.into(),
+            };
+            for rule in &mut self.rules {
+                rule.check_message(rb, message, &row);
+            }
+        } else {
+            error!(
+                "In DumpPluginDataCheck::load_message, the block is not DataCheckContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(mut rb) = block.into_any().downcast::<DataCheckContent>() {
+            for rule in &mut self.rules {
+                rule.finish_block(&mut rb);
+            }
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginDataCheck::push_block, the block is not DataCheckContent, ignore this message"
+            );
+        }
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        let mut ret = Vec::with_capacity(self.content.len());
+        for check_result in &self.content {
+            ret.push(check_result.to_json(self.header_to_json(check_result.head.as_ref())));
+        }
+        ret
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        for check_result in &self.content {
+            ret.extend(check_result.to_text());
+        }
+        ret
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = Ok(());
+        if !self.write_to_text_file.is_empty()
+            && let Err(e) = self.dump_to_text_file(&self.write_to_text_file)
+        {
+            ret = Err(e);
+        }
+
+        if !self.write_to_json_file.is_empty()
+            && let Err(e) = self.dump_to_json_file(&self.write_to_json_file, self.output_pretty)
+        {
+            ret = Err(e);
+        }
+
+        for rule in &self.rules {
+            if rule.has_error() {
+                ret = Err(());
+            }
+        }
+
+        let violation_count: usize = self.content.iter().map(|c| c.violations.len()).sum();
+        if violation_count > 0 {
+            error!("Data check found {} violation(s)", violation_count);
+            ret = Err(());
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::data_check::{self, DataCheckContent, DataCheckRow, DataCheckRule};
+use super::dump_options::DumpOptions;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::utility;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{FieldDescriptor, ReflectValueRef};
+use xresloader_protocol::proto::xresloader::{ListStripOption, exts};
+
+struct ListSizeRule {
+    min_size: Option<usize>,
+    max_size: Option<usize>,
+    strict_size: bool,
+    strip_option: ListStripOption,
+}
+
+impl ListSizeRule {
+    fn parse_size(option_name: &str, value: Option<String>) -> Result<Option<usize>, String> {
+        match value {
+            Some(x) if !x.trim().is_empty() => match x.trim().parse::<usize>() {
+                Ok(size) => Ok(Some(size)),
+                Err(e) => Err(format!(
+                    "invalid org.xresloader.{} \"{}\", {}",
+                    option_name, x, e
+                )),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn build(field: &FieldDescriptor) -> Result<Option<ListSizeRule>, String> {
+        let options = match field.proto().options.as_ref() {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let min_size = Self::parse_size(
+            "field_list_min_size",
+            exts::field_list_min_size.get(options),
+        )?;
+        let max_size = Self::parse_size(
+            "field_list_max_size",
+            exts::field_list_max_size.get(options),
+        )?;
+        if min_size.is_none() && max_size.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(ListSizeRule {
+            min_size,
+            max_size,
+            strict_size: exts::field_list_strict_size.get(options).unwrap_or(false),
+            strip_option: exts::field_list_strip_option
+                .get(options)
+                .and_then(|x| x.enum_value().ok())
+                .unwrap_or(ListStripOption::LIST_STRIP_DEFAULT),
+        }))
+    }
+
+    /// Count elements filled by designers, empty elements are stripped by xresloader according to strip option
+    fn count_filled_elements(&self, values: &[ReflectValueRef]) -> usize {
+        match self.strip_option {
+            ListStripOption::LIST_STRIP_NOTHING => values.len(),
+            ListStripOption::LIST_STRIP_TAIL => {
+                values.len()
+                    - values
+                        .iter()
+                        .rev()
+                        .take_while(|v| utility::is_pb_value_empty(v))
+                        .count()
+            }
+            ListStripOption::LIST_STRIP_DEFAULT | ListStripOption::LIST_STRIP_ALL => values
+                .iter()
+                .filter(|v| !utility::is_pb_value_empty(v))
+                .count(),
+        }
+    }
+}
+
+pub struct DataCheckListSize {
+    cache: HashMap<String, Option<Rc<ListSizeRule>>>,
+    has_error: bool,
+}
+
+impl DataCheckListSize {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn DataCheckRule>>, bool) {
+        if !args.check_list_size {
+            return (None, false);
+        }
+
+        (
+            Some(Box::new(DataCheckListSize {
+                cache: HashMap::new(),
+                has_error: false,
+            })),
+            false,
+        )
+    }
+
+    fn get_rule(&mut self, field: &FieldDescriptor) -> Option<Rc<ListSizeRule>> {
+        let full_name = field.full_name();
+        if let Some(x) = self.cache.get(&full_name) {
+            return x.clone();
+        }
+
+        let ret = match ListSizeRule::build(field) {
+            Ok(x) => x.map(Rc::new),
+            Err(e) => {
+                error!("Field {} has {}", full_name, e);
+                self.has_error = true;
+                None
+            }
+        };
+        self.cache.insert(full_name, ret.clone());
+        ret
+    }
+
+    fn check_fields(
+        &mut self,
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        path: &str,
+        row: &DataCheckRow,
+    ) {
+        for field in message.descriptor_dyn().fields() {
+            if !field.is_repeated() {
+                continue;
+            }
+
+            let rule = match self.get_rule(&field) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            let values: Vec<ReflectValueRef> = field.get_repeated(message).into_iter().collect();
+            let size = values.len();
+            if let Some(min_size) = rule.min_size {
+                let filled_size = if rule.strict_size {
+                    rule.count_filled_elements(&values)
+                } else {
+                    size
+                };
+                if filled_size < min_size {
+                    block.add_violation(
+                        "list_size",
+                        row,
+                        data_check::join_field_path(path, field.name()),
+                        filled_size.to_string(),
+                        if rule.strict_size {
+                            format!(
+                                "list has {} filled element(s), less than org.xresloader.field_list_min_size {} with org.xresloader.field_list_strict_size",
+                                filled_size, min_size
+                            )
+                        } else {
+                            format!(
+                                "list has {} element(s), less than org.xresloader.field_list_min_size {}",
+                                filled_size, min_size
+                            )
+                        },
+                    );
+                }
+            }
+
+            if let Some(max_size) = rule.max_size
+                && size > max_size
+            {
+                block.add_violation(
+                    "list_size",
+                    row,
+                    data_check::join_field_path(path, field.name()),
+                    size.to_string(),
+                    format!(
+                        "list has {} element(s), more than org.xresloader.field_list_max_size {}",
+                        size, max_size
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl DataCheckRule for DataCheckListSize {
+    fn check_message(
+        &mut self,
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        row: &DataCheckRow,
+    ) {
+        data_check::for_each_nested_message(message, "", &mut |sub_message, path| {
+            self.check_fields(block, sub_message, path, row);
+        });
+    }
+
+    fn has_error(&self) -> bool {
+        self.has_error
+    }
+}
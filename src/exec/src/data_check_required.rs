@@ -0,0 +1,98 @@
+use super::data_check::{self, DataCheckContent, DataCheckRow, DataCheckRule};
+use super::dump_options::DumpOptions;
+use super::file_descriptor_index::FileDescriptorIndex;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{FieldDescriptor, ReflectFieldRef};
+use xresloader_protocol::proto::xresloader::exts;
+
+pub struct DataCheckRequired {}
+
+fn is_field_null(field: &FieldDescriptor, message: &dyn MessageDyn) -> bool {
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(v) => v.value().is_none(),
+        ReflectFieldRef::Repeated(v) => v.is_empty(),
+        ReflectFieldRef::Map(v) => v.is_empty(),
+    }
+}
+
+impl DataCheckRequired {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn DataCheckRule>>, bool) {
+        if !args.check_required {
+            return (None, false);
+        }
+
+        (Some(Box::new(DataCheckRequired {})), false)
+    }
+
+    fn check_fields(
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        path: &str,
+        row: &DataCheckRow,
+    ) {
+        for field in message.descriptor_dyn().fields() {
+            let options = match field.proto().options.as_ref() {
+                Some(x) => x,
+                None => continue,
+            };
+
+            let (rule, option_name) = if exts::field_required.get(options).unwrap_or(false) {
+                ("required", "field_required")
+            } else if exts::field_not_null.get(options).unwrap_or(false) {
+                ("not_null", "field_not_null")
+            } else {
+                continue;
+            };
+
+            if is_field_null(&field, message) {
+                block.add_violation(
+                    rule,
+                    row,
+                    data_check::join_field_path(path, field.name()),
+                    String::new(),
+                    format!(
+                        "field with org.xresloader.{} can not be null or empty",
+                        option_name
+                    ),
+                );
+            }
+        }
+
+        for oneof in message.descriptor_dyn().oneofs() {
+            let not_null = match oneof.proto().options.as_ref() {
+                Some(options) => exts::oneof_not_null.get(options).unwrap_or(false),
+                None => false,
+            };
+            if !not_null {
+                continue;
+            }
+
+            if oneof.fields().all(|field| !field.has_field(message)) {
+                block.add_violation(
+                    "not_null",
+                    row,
+                    data_check::join_field_path(path, oneof.name()),
+                    String::new(),
+                    "oneof with org.xresloader.oneof_not_null must have one field set".to_string(),
+                );
+            }
+        }
+    }
+}
+
+impl DataCheckRule for DataCheckRequired {
+    fn check_message(
+        &mut self,
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        row: &DataCheckRow,
+    ) {
+        data_check::for_each_nested_message(message, "", &mut |sub_message, path| {
+            Self::check_fields(block, sub_message, path, row);
+        });
+    }
+}
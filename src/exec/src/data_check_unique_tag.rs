@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::data_check::{DataCheckContent, DataCheckRow, DataCheckRule};
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::utility;
+
+use protobuf::reflect::{FieldDescriptor, MessageDescriptor};
+use protobuf::{Message, MessageDyn};
+
+// field_unique_tag.field_number is 1002 and it's private
+// FIXME: use a public API to get field number after upgrade to protobuf v4+
+pub const FIELD_UNIQUE_TAG_NUMBER: u32 = 1002;
+
+struct UniqueTagGroup {
+    tag: String,
+    fields: Vec<FieldDescriptor>,
+}
+
+struct UniqueTagRow {
+    row_index: usize,
+    data_source: Rc<dump_plugin::DumpPluginItemDataSource>,
+}
+
+#[derive(Default)]
+pub struct DataCheckUniqueTag {
+    groups: HashMap<String, Rc<Vec<UniqueTagGroup>>>,
+    // groups of the message type in current block, binary files of different types may use the same tag
+    block_groups: Option<Rc<Vec<UniqueTagGroup>>>,
+    // tag -> key values -> rows
+    keys: HashMap<String, HashMap<Vec<String>, Vec<UniqueTagRow>>>,
+}
+
+fn join_key_items(items: &[String]) -> String {
+    if items.len() == 1 {
+        items[0].clone()
+    } else {
+        format!("({})", items.join(", "))
+    }
+}
+
+impl DataCheckUniqueTag {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn DataCheckRule>>, bool) {
+        if !args.check_unique_tag {
+            return (None, false);
+        }
+
+        (Some(Box::new(DataCheckUniqueTag::default())), false)
+    }
+
+    fn get_groups(&mut self, message_desc: &MessageDescriptor) -> Rc<Vec<UniqueTagGroup>> {
+        if let Some(x) = self.groups.get(message_desc.full_name()) {
+            return x.clone();
+        }
+
+        let mut groups: Vec<UniqueTagGroup> = Vec::new();
+        for field in message_desc.fields() {
+            let tags = match field.proto().options.as_ref() {
+                Some(options) => utility::get_repeated_string_option(
+                    options.unknown_fields(),
+                    FIELD_UNIQUE_TAG_NUMBER,
+                ),
+                None => continue,
+            };
+
+            for tag in tags {
+                if tag.is_empty() {
+                    continue;
+                }
+                if let Some(group) = groups.iter_mut().find(|g| g.tag == tag) {
+                    group.fields.push(field.clone());
+                } else {
+                    groups.push(UniqueTagGroup {
+                        tag,
+                        fields: vec![field.clone()],
+                    });
+                }
+            }
+        }
+
+        let ret = Rc::new(groups);
+        self.groups
+            .insert(message_desc.full_name().to_string(), ret.clone());
+        ret
+    }
+}
+
+impl DataCheckRule for DataCheckUniqueTag {
+    fn check_message(
+        &mut self,
+        _block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        row: &DataCheckRow,
+    ) {
+        let groups = self.get_groups(&message.descriptor_dyn());
+        for group in groups.iter() {
+            let key: Vec<String> = group
+                .fields
+                .iter()
+                .map(|field| utility::pb_field_value_to_string(field, message))
+                .collect();
+
+            self.keys
+                .entry(group.tag.clone())
+                .or_default()
+                .entry(key)
+                .or_default()
+                .push(UniqueTagRow {
+                    row_index: row.row_index,
+                    data_source: row.data_source.clone(),
+                });
+        }
+        self.block_groups = Some(groups);
+    }
+
+    fn finish_block(&mut self, block: &mut DataCheckContent) {
+        let keys = std::mem::take(&mut self.keys);
+        let groups = self.block_groups.take();
+        utility::for_each_ordered_hash_map(&keys, |tag, key_rows| {
+            let mut duplicated: Vec<(&Vec<String>, &Vec<UniqueTagRow>)> =
+                key_rows.iter().filter(|(_, rows)| rows.len() > 1).collect();
+            duplicated.sort_by_key(|(_, rows)| rows[0].row_index);
+
+            let field_names = groups
+                .iter()
+                .flat_map(|groups| groups.iter())
+                .find(|group| &group.tag == tag)
+                .map(|group| {
+                    join_key_items(
+                        &group
+                            .fields
+                            .iter()
+                            .map(|field| field.name().to_string())
+                            .collect::<Vec<String>>(),
+                    )
+                })
+                .unwrap_or_default();
+
+            for (key, rows) in duplicated {
+                let row_list = rows
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "row {}({}|{})",
+                            r.row_index, r.data_source.file, r.data_source.sheet
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                block.add_violation(
+                    "unique_tag",
+                    &DataCheckRow {
+                        row_index: rows[1].row_index,
+                        data_source: rows[1].data_source.clone(),
+                    },
+                    field_names.clone(),
+                    join_key_items(key),
+                    format!(
+                        "duplicated key of org.xresloader.field_unique_tag \"{}\" in {}",
+                        tag, row_list
+                    ),
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,632 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::data_check::{self, DataCheckContent, DataCheckRow, DataCheckRule};
+use super::dump_options::DumpOptions;
+use super::file_descriptor_index::{
+    EnumDescriptorCacheRef, FileDescriptorIndex, MessageDescriptorCacheRef,
+};
+use super::row_flatten;
+use super::utility;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{FieldDescriptor, ReflectValueRef, RuntimeFieldType, RuntimeType};
+use xresloader_protocol::proto::xresloader::exts;
+
+// Custom validators can reference each other, this is used to stop recursive references
+const MAX_CUSTOM_VALIDATOR_DEPTH: usize = 16;
+
+#[derive(Clone, Copy)]
+enum ValidatorNumber {
+    Integer(i128),
+    Float(f64),
+}
+
+impl ValidatorNumber {
+    fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if let Ok(x) = input.parse::<i128>() {
+            return Some(ValidatorNumber::Integer(x));
+        }
+        if let Ok(x) = input.parse::<f64>()
+            && x.is_finite()
+        {
+            return Some(ValidatorNumber::Float(x));
+        }
+        None
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            ValidatorNumber::Integer(x) => *x as f64,
+            ValidatorNumber::Float(x) => *x,
+        }
+    }
+
+    fn compare(&self, other: &ValidatorNumber) -> Option<Ordering> {
+        match (self, other) {
+            (ValidatorNumber::Integer(l), ValidatorNumber::Integer(r)) => Some(l.cmp(r)),
+            _ => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+enum ValidatorValue<'a> {
+    Number(ValidatorNumber),
+    Text(&'a str),
+}
+
+impl<'a> ValidatorValue<'a> {
+    fn from_reflect_value(value: &ReflectValueRef<'a>) -> Option<Self> {
+        match value {
+            ReflectValueRef::U32(x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Integer(*x as i128)))
+            }
+            ReflectValueRef::U64(x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Integer(*x as i128)))
+            }
+            ReflectValueRef::I32(x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Integer(*x as i128)))
+            }
+            ReflectValueRef::I64(x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Integer(*x as i128)))
+            }
+            ReflectValueRef::F32(x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Float(*x as f64)))
+            }
+            ReflectValueRef::F64(x) => Some(ValidatorValue::Number(ValidatorNumber::Float(*x))),
+            ReflectValueRef::Bool(x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Integer(if *x {
+                    1
+                } else {
+                    0
+                })))
+            }
+            ReflectValueRef::Enum(_, x) => {
+                Some(ValidatorValue::Number(ValidatorNumber::Integer(*x as i128)))
+            }
+            ReflectValueRef::String(s) => Some(ValidatorValue::Text(s)),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<ValidatorNumber> {
+        match self {
+            ValidatorValue::Number(x) => Some(*x),
+            ValidatorValue::Text(s) => ValidatorNumber::parse(s),
+        }
+    }
+}
+
+enum ValidatorRule {
+    /// min-max, both inclusive
+    Range(ValidatorNumber, ValidatorNumber),
+    /// >, >=, <, <=, ==
+    Compare(Ordering, bool, ValidatorNumber),
+    /// Number or name of enum values, message fields or fields in oneof
+    ValueSet(HashSet<i128>, HashSet<String>),
+}
+
+impl ValidatorRule {
+    fn check(&self, value: &ValidatorValue) -> bool {
+        match self {
+            ValidatorRule::Range(min, max) => match value.as_number() {
+                Some(x) => {
+                    x.compare(min).is_some_and(|o| o != Ordering::Less)
+                        && x.compare(max).is_some_and(|o| o != Ordering::Greater)
+                }
+                None => false,
+            },
+            ValidatorRule::Compare(expect, allow_equal, target) => match value.as_number() {
+                Some(x) => match x.compare(target) {
+                    Some(Ordering::Equal) => *allow_equal,
+                    Some(o) => o == *expect,
+                    None => false,
+                },
+                None => false,
+            },
+            ValidatorRule::ValueSet(numbers, names) => match value {
+                ValidatorValue::Number(ValidatorNumber::Integer(x)) => numbers.contains(x),
+                ValidatorValue::Number(ValidatorNumber::Float(_)) => false,
+                ValidatorValue::Text(s) => {
+                    if names.contains(*s) {
+                        return true;
+                    }
+                    match ValidatorNumber::parse(s) {
+                        Some(ValidatorNumber::Integer(x)) => numbers.contains(&x),
+                        _ => false,
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// A validator passes if any of its rules passes
+struct Validator {
+    expression: String,
+    rules: Vec<ValidatorRule>,
+}
+
+impl Validator {
+    fn check(&self, value: &ValidatorValue) -> bool {
+        self.rules.iter().any(|rule| rule.check(value))
+    }
+}
+
+pub struct DataCheckValidator {
+    enums: HashMap<String, EnumDescriptorCacheRef>,
+    messages: HashMap<String, MessageDescriptorCacheRef>,
+    custom_validators: HashMap<String, String>,
+    cache: HashMap<String, Option<Rc<Validator>>>,
+    include_default: bool,
+    has_error: bool,
+}
+
+impl DataCheckValidator {
+    pub fn build(
+        args: &DumpOptions,
+        desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn DataCheckRule>>, bool) {
+        if !args.check_validator {
+            return (None, false);
+        }
+
+        let mut has_error = false;
+        let mut custom_validators = HashMap::new();
+        for file_path in &args.validator_rules_file {
+            utility::load_file_by_lines(file_path, "validator rule", &mut has_error, |line| {
+                match line.split_once(':') {
+                    Some((name, expression)) if !name.trim().is_empty() => {
+                        custom_validators
+                            .insert(name.trim().to_string(), expression.trim().to_string());
+                        Ok(())
+                    }
+                    _ => Err("validator rule should be <NAME>: <VALIDATOR>".to_string()),
+                }
+            });
+        }
+
+        if has_error {
+            return (None, has_error);
+        }
+
+        (
+            Some(Box::new(DataCheckValidator {
+                enums: desc_index.enums.clone(),
+                messages: desc_index.messages.clone(),
+                custom_validators,
+                cache: HashMap::new(),
+                include_default: args.check_validator_include_default,
+                has_error: false,
+            })),
+            false,
+        )
+    }
+
+    /// Resolve type name like protobuf, from the innermost scope(full name of the message) to the outermost one
+    fn find_by_type_name<'a, T>(
+        index: &'a HashMap<String, T>,
+        type_name: &str,
+        scope: &str,
+    ) -> Option<&'a T> {
+        if let Some(x) = type_name.strip_prefix('.') {
+            return index.get(x);
+        }
+
+        let mut scope = scope;
+        while !scope.is_empty() {
+            if let Some(x) = index.get(&format!("{}.{}", scope, type_name)) {
+                return Some(x);
+            }
+            scope = scope.rsplit_once('.').map(|x| x.0).unwrap_or_default();
+        }
+
+        if let Some(x) = index.get(type_name) {
+            return Some(x);
+        }
+
+        // Short name is allowed if it's unique
+        let suffix = format!(".{}", type_name);
+        let mut found = None;
+        for (full_name, x) in index {
+            if full_name.ends_with(&suffix) {
+                if found.is_some() {
+                    return None;
+                }
+                found = Some(x);
+            }
+        }
+        found
+    }
+
+    fn compile_type_name(&self, type_name: &str, scope: &str) -> Option<ValidatorRule> {
+        let mut numbers = HashSet::new();
+        let mut names = HashSet::new();
+
+        if let Some(enum_desc) = Self::find_by_type_name(&self.enums, type_name, scope) {
+            for value in &enum_desc.proto.value {
+                numbers.insert(value.number() as i128);
+                names.insert(value.name().to_string());
+            }
+            return Some(ValidatorRule::ValueSet(numbers, names));
+        }
+
+        if let Some(message_desc) = Self::find_by_type_name(&self.messages, type_name, scope) {
+            for field in &message_desc.proto.field {
+                numbers.insert(field.number() as i128);
+                names.insert(field.name().to_string());
+            }
+            return Some(ValidatorRule::ValueSet(numbers, names));
+        }
+
+        // Message.oneof_name
+        let (message_name, oneof_name) = type_name.rsplit_once('.')?;
+        let message_desc = Self::find_by_type_name(&self.messages, message_name, scope)?;
+        let oneof_index = message_desc
+            .proto
+            .oneof_decl
+            .iter()
+            .position(|oneof| oneof.name() == oneof_name)?;
+        for field in &message_desc.proto.field {
+            if field.has_oneof_index() && field.oneof_index() as usize == oneof_index {
+                numbers.insert(field.number() as i128);
+                names.insert(field.name().to_string());
+            }
+        }
+        Some(ValidatorRule::ValueSet(numbers, names))
+    }
+
+    fn compile_rules(
+        &self,
+        expression: &str,
+        scope: &str,
+        depth: usize,
+        rules: &mut Vec<ValidatorRule>,
+    ) -> Result<(), String> {
+        if depth > MAX_CUSTOM_VALIDATOR_DEPTH {
+            return Err(format!(
+                "custom validator nested too deep when parsing \"{}\"",
+                expression
+            ));
+        }
+
+        for rule in expression.split(['|', ',']) {
+            let rule = rule.trim();
+            if rule.is_empty() {
+                continue;
+            }
+
+            let compare_rules = [
+                (">=", Ordering::Greater, true),
+                ("<=", Ordering::Less, true),
+                ("==", Ordering::Equal, true),
+                (">", Ordering::Greater, false),
+                ("<", Ordering::Less, false),
+                ("=", Ordering::Equal, true),
+            ];
+            if let Some((prefix, expect, allow_equal)) = compare_rules
+                .iter()
+                .find(|(prefix, _, _)| rule.starts_with(prefix))
+            {
+                match ValidatorNumber::parse(&rule[prefix.len()..]) {
+                    Some(x) => {
+                        rules.push(ValidatorRule::Compare(*expect, *allow_equal, x));
+                        continue;
+                    }
+                    None => {
+                        return Err(format!("invalid number in \"{}\"", rule));
+                    }
+                }
+            }
+
+            if let Some(x) = ValidatorNumber::parse(rule) {
+                rules.push(ValidatorRule::Range(x, x));
+                continue;
+            }
+
+            // min-max, min and max can be negative, so we skip the first character to find the separator
+            if let Some(pos) = rule
+                .char_indices()
+                .skip(1)
+                .find(|(_, c)| *c == '-')
+                .map(|x| x.0)
+                && let (Some(min), Some(max)) = (
+                    ValidatorNumber::parse(&rule[..pos]),
+                    ValidatorNumber::parse(&rule[pos + 1..]),
+                )
+            {
+                rules.push(ValidatorRule::Range(min, max));
+                continue;
+            }
+
+            if let Some(custom_expression) = self.custom_validators.get(rule) {
+                self.compile_rules(custom_expression, scope, depth + 1, rules)?;
+                continue;
+            }
+
+            match self.compile_type_name(rule, scope) {
+                Some(x) => rules.push(x),
+                None => {
+                    return Err(format!(
+                        "\"{}\" is not a range, custom validator, enum or message",
+                        rule
+                    ));
+                }
+            }
+        }
+
+        if rules.is_empty() {
+            return Err("empty validator".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn get_validator(
+        &mut self,
+        field: &FieldDescriptor,
+        option_name: &str,
+        expression: &str,
+    ) -> Option<Rc<Validator>> {
+        let cache_key = format!("{}|{}", field.full_name(), option_name);
+        if let Some(x) = self.cache.get(&cache_key) {
+            return x.clone();
+        }
+
+        let scope = field.containing_message().full_name().to_string();
+        let mut rules = Vec::new();
+        let ret = match self.compile_rules(expression, &scope, 0, &mut rules) {
+            Ok(_) => Some(Rc::new(Validator {
+                expression: expression.to_string(),
+                rules,
+            })),
+            Err(e) => {
+                error!(
+                    "Invalid org.xresloader.{} \"{}\" of field {}, {}",
+                    option_name,
+                    expression,
+                    field.full_name(),
+                    e
+                );
+                self.has_error = true;
+                None
+            }
+        };
+
+        self.cache.insert(cache_key, ret.clone());
+        ret
+    }
+
+    fn check_value(
+        block: &mut DataCheckContent,
+        row: &DataCheckRow,
+        option_name: &str,
+        validator: &Validator,
+        value: &ReflectValueRef,
+        field_path: String,
+    ) {
+        if let Some(v) = ValidatorValue::from_reflect_value(value)
+            && !validator.check(&v)
+        {
+            block.add_violation(
+                "validator",
+                row,
+                field_path,
+                utility::pb_value_to_string(value),
+                format!(
+                    "value is not matched by org.xresloader.{} \"{}\"",
+                    option_name, validator.expression
+                ),
+            );
+        }
+    }
+
+    fn check_field(
+        &mut self,
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        field: &FieldDescriptor,
+        path: &str,
+        row: &DataCheckRow,
+    ) {
+        let options = match field.proto().options.as_ref() {
+            Some(x) => x,
+            None => return,
+        };
+
+        let mut expressions = vec![
+            ("validator", exts::validator.get(options)),
+            ("verifier", exts::verifier.get(options)),
+        ];
+        // map_key_validator and map_value_validator only apply to map fields
+        if let RuntimeFieldType::Map(_, _) = field.runtime_field_type() {
+            expressions.push((
+                "map_value_validator",
+                exts::map_value_validator.get(options),
+            ));
+            expressions.push(("map_key_validator", exts::map_key_validator.get(options)));
+        }
+
+        let mut value_validators = Vec::new();
+        let mut key_validators = Vec::new();
+        for (option_name, expression) in expressions {
+            let expression = match expression {
+                Some(x) if !x.trim().is_empty() => x,
+                _ => continue,
+            };
+
+            if let Some(validator) = self.get_validator(field, option_name, &expression) {
+                if option_name == "map_key_validator" {
+                    key_validators.push((option_name, validator));
+                } else {
+                    value_validators.push((option_name, validator));
+                }
+            }
+        }
+
+        if value_validators.is_empty() && key_validators.is_empty() {
+            return;
+        }
+
+        let field_path = data_check::join_field_path(path, field.name());
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(t) => {
+                // Empty cells are not validated by xresloader, and we can not tell them from default values of proto3.
+                // Default values are checked with --check-validator-include-default, unless the field has presence
+                let value = if field.has_field(message) {
+                    field.get_singular(message)
+                } else if self.include_default
+                    && !matches!(t, RuntimeType::Message(_))
+                    && field.containing_oneof_including_synthetic().is_none()
+                {
+                    Some(field.get_singular_field_or_default(message))
+                } else {
+                    None
+                };
+                if let Some(v) = value {
+                    for (option_name, validator) in &value_validators {
+                        Self::check_value(
+                            block,
+                            row,
+                            option_name,
+                            validator,
+                            &v,
+                            field_path.clone(),
+                        );
+                    }
+                }
+            }
+            RuntimeFieldType::Repeated(_) => {
+                for (index, v) in field.get_repeated(message).into_iter().enumerate() {
+                    for (option_name, validator) in &value_validators {
+                        Self::check_value(
+                            block,
+                            row,
+                            option_name,
+                            validator,
+                            &v,
+                            format!("{}[{}]", field_path, index),
+                        );
+                    }
+                }
+            }
+            RuntimeFieldType::Map(_, _) => {
+                // Keep stable order of map, so reports can be compared
+                let map = field.get_map(message);
+                let mut entries: Vec<(ReflectValueRef, ReflectValueRef)> =
+                    (&map).into_iter().collect();
+                entries.sort_by_key(|(k, _)| row_flatten::map_key_to_flatten_key(k));
+                for (k, v) in entries {
+                    let item_path =
+                        format!("{}[{}]", field_path, data_check::map_key_to_path_index(&k));
+                    for (option_name, validator) in &key_validators {
+                        Self::check_value(
+                            block,
+                            row,
+                            option_name,
+                            validator,
+                            &k,
+                            item_path.clone(),
+                        );
+                    }
+                    for (option_name, validator) in &value_validators {
+                        Self::check_value(
+                            block,
+                            row,
+                            option_name,
+                            validator,
+                            &v,
+                            item_path.clone(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl DataCheckRule for DataCheckValidator {
+    fn check_message(
+        &mut self,
+        block: &mut DataCheckContent,
+        message: &dyn MessageDyn,
+        row: &DataCheckRow,
+    ) {
+        data_check::for_each_nested_message(message, "", &mut |sub_message, path| {
+            for field in sub_message.descriptor_dyn().fields() {
+                self.check_field(block, sub_message, &field, path, row);
+            }
+        });
+    }
+
+    fn has_error(&self) -> bool {
+        self.has_error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_index(names: &[&str]) -> HashMap<String, String> {
+        names
+            .iter()
+            .map(|x| (x.to_string(), x.to_string()))
+            .collect()
+    }
+
+    fn find(index: &HashMap<String, String>, type_name: &str, scope: &str) -> Option<String> {
+        DataCheckValidator::find_by_type_name(index, type_name, scope).cloned()
+    }
+
+    #[test]
+    fn find_type_name_in_package_before_short_name() {
+        // Short name Kind is ambiguous because of google.protobuf.Field.Kind
+        let index = build_index(&["demo.Kind", "google.protobuf.Field.Kind"]);
+        assert_eq!(
+            find(&index, "Kind", "demo.Item"),
+            Some("demo.Kind".to_string())
+        );
+        assert_eq!(find(&index, "Kind", "other.Item"), None);
+    }
+
+    #[test]
+    fn find_type_name_in_enclosing_scopes() {
+        let index = build_index(&["demo.Kind", "demo.Item.Kind", "demo.Item.Sub"]);
+        assert_eq!(
+            find(&index, "Kind", "demo.Item.Sub"),
+            Some("demo.Item.Kind".to_string())
+        );
+        assert_eq!(
+            find(&index, "Kind", "demo.Other"),
+            Some("demo.Kind".to_string())
+        );
+        assert_eq!(
+            find(&index, "Item.Kind", "demo.Other"),
+            Some("demo.Item.Kind".to_string())
+        );
+    }
+
+    #[test]
+    fn find_type_name_fully_qualified() {
+        let index = build_index(&["demo.Kind", "demo.Item.Kind"]);
+        assert_eq!(
+            find(&index, ".demo.Kind", "demo.Item"),
+            Some("demo.Kind".to_string())
+        );
+        assert_eq!(find(&index, ".Kind", "demo.Item"), None);
+        assert_eq!(find(&index, "demo.Kind", ""), Some("demo.Kind".to_string()));
+    }
+
+    #[test]
+    fn find_type_name_by_unique_short_name() {
+        let index = build_index(&["demo.Kind", "other.Cost"]);
+        assert_eq!(
+            find(&index, "Cost", "demo.Item"),
+            Some("other.Cost".to_string())
+        );
+        assert_eq!(find(&index, "Missing", "demo.Item"), None);
+    }
+}
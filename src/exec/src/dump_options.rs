@@ -32,6 +32,18 @@ pub struct DumpOptions {
     #[arg(long, value_parser, default_value = "false")]
     pub silence: bool,
 
+    /// Only print and dump rows matching the expression, such as "Id == 10001 && Level > 5"(can be used mulpitle times)
+    #[arg(long = "where", value_parser, action = ArgAction::Append, value_name = "EXPRESSION")]
+    pub where_expr: Vec<String>,
+
+    /// Only print selected fields of rows, such as "Cost.*" and "Rewards[0].Id"(can be used mulpitle times or split by ',')
+    #[arg(long, value_parser, action = ArgAction::Append, value_name = "FIELD PATH")]
+    pub select: Vec<String>,
+
+    /// Verify hash code in header of binary files
+    #[arg(long, value_parser, default_value = "false")]
+    pub verify_hash: bool,
+
     /// Output string table as json
     #[arg(
         long,
@@ -90,6 +102,111 @@ pub struct DumpOptions {
     #[arg(long, value_parser, default_value = "false")]
     pub string_table_ordered: bool,
 
+    /// Load string table json generated by --output-string-table-json before as baseline of delta output
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "BASELINE JSON FILE PATH"
+    )]
+    pub string_table_baseline_json: String,
+
+    /// Output added, removed and source changed strings relative to --string-table-baseline-json as json
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT JSON FILE PATH"
+    )]
+    pub output_string_table_delta_json: String,
+
+    /// Output added, removed and source changed strings relative to --string-table-baseline-json as text lines
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT TEXT FILE PATH"
+    )]
+    pub output_string_table_delta_text: String,
+
+    /// Output string table as gettext PO file, msgctxt is the full name of field
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT PO FILE PATH"
+    )]
+    pub output_string_table_po: String,
+
+    /// Output string table as gettext POT template, msgctxt is the full name of field
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT POT FILE PATH"
+    )]
+    pub output_string_table_pot: String,
+
+    /// Language in header of --output-string-table-po
+    #[arg(long, value_parser, default_value = "", value_name = "LANGUAGE")]
+    pub string_table_po_language: String,
+
+    /// Output string table as XLIFF, one file element for each binary file
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT XLIFF FILE PATH"
+    )]
+    pub output_string_table_xliff: String,
+
+    /// Version of --output-string-table-xliff, 1.2 or 2.0
+    #[arg(long, value_parser, default_value = "1.2", value_name = "1.2|2.0")]
+    pub string_table_xliff_version: String,
+
+    /// Source language of --output-string-table-xliff
+    #[arg(long, value_parser, default_value = "en", value_name = "LANGUAGE")]
+    pub string_table_xliff_source_language: String,
+
+    /// Target language of --output-string-table-xliff
+    #[arg(long, value_parser, default_value = "", value_name = "LANGUAGE")]
+    pub string_table_xliff_target_language: String,
+
+    /// Output string table as csv of UE StringTable, it's a directory when --string-table-ue-namespace-by-message is set
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT CSV FILE OR DIRECTORY"
+    )]
+    pub output_string_table_ue_csv: String,
+
+    /// Key of UE StringTable, hash: hash of source string, path: field path and row key, index: running index
+    #[arg(
+        long,
+        value_parser,
+        default_value = "hash",
+        value_name = "hash|path|index"
+    )]
+    pub string_table_ue_key: String,
+
+    /// Encoding of UE StringTable, utf8 or utf16le(with BOM)
+    #[arg(
+        long,
+        value_parser,
+        default_value = "utf8",
+        value_name = "utf8|utf16le"
+    )]
+    pub string_table_ue_encoding: String,
+
+    /// Add Comment column with sources of strings into UE StringTable
+    #[arg(long, value_parser, default_value = "false")]
+    pub string_table_ue_comment: bool,
+
+    /// Write one UE StringTable for each message type, named <message type>.csv, the message type can be used as namespace
+    #[arg(long, value_parser, default_value = "false")]
+    pub string_table_ue_namespace_by_message: bool,
+
     /// Output tagged field as json
     #[arg(
         long,
@@ -155,4 +272,302 @@ pub struct DumpOptions {
     /// Tagged data ordered mode
     #[arg(long, value_parser, default_value = "false")]
     pub tagged_data_ordered: bool,
+
+    /// Check rows by org.xresloader.validator, org.xresloader.verifier, org.xresloader.map_key_validator and org.xresloader.map_value_validator
+    #[arg(long, value_parser, default_value = "false")]
+    pub check_validator: bool,
+
+    /// Also check unset fields by their default values(like 0 or ""), except message fields and fields in oneof
+    #[arg(long, value_parser, default_value = "false")]
+    pub check_validator_include_default: bool,
+
+    /// Check uniqueness of fields with the same org.xresloader.field_unique_tag in each binary file
+    #[arg(long, value_parser, default_value = "false")]
+    pub check_unique_tag: bool,
+
+    /// Check fields with org.xresloader.field_not_null or org.xresloader.field_required and oneofs with org.xresloader.oneof_not_null are not empty
+    #[arg(long, value_parser, default_value = "false")]
+    pub check_required: bool,
+
+    /// Check sizes of repeated fields by org.xresloader.field_list_min_size, org.xresloader.field_list_max_size, org.xresloader.field_list_strict_size and org.xresloader.field_list_strip_option
+    #[arg(long, value_parser, default_value = "false")]
+    pub check_list_size: bool,
+
+    /// Load custom validators from file, each line is "<NAME>: <VALIDATOR>"(can be used mulpitle times)
+    #[arg(long, value_parser, action = ArgAction::Append, value_name = "FILE PATH")]
+    pub validator_rules_file: Vec<String>,
+
+    /// Output data check report as json
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT JSON FILE PATH"
+    )]
+    pub output_check_report_json: String,
+
+    /// Output data check report as text lines
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT TEXT FILE PATH"
+    )]
+    pub output_check_report_text: String,
+
+    /// Data check report pretty mode
+    #[arg(long, value_parser, default_value = "false")]
+    pub check_report_pretty: bool,
+
+    /// Output rows as flattened csv/tsv, write into <DIR>/<name of binary file>.csv if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_csv: String,
+
+    /// Format of --output-csv, csv or tsv
+    #[arg(long, value_parser, default_value = "csv", value_name = "csv|tsv")]
+    pub csv_format: String,
+
+    /// Add row index column(_row) in front of --output-csv
+    #[arg(long, value_parser, default_value = "false")]
+    pub csv_with_row_index: bool,
+
+    /// Add source file and sheet columns(_file, _sheet) in front of --output-csv
+    #[arg(long, value_parser, default_value = "false")]
+    pub csv_with_source: bool,
+
+    /// Output rows as xlsx workbook, write into <DIR>/<name of binary file>.xlsx if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_xlsx: String,
+
+    /// Write all rows of a binary file into one sheet of --output-xlsx, instead of one sheet for each data source sheet
+    #[arg(long, value_parser, default_value = "false")]
+    pub xlsx_single_sheet: bool,
+
+    /// Output header and rows as a json document, write into <DIR>/<name of binary file>.json if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_json: String,
+
+    /// Pretty mode of --output-json
+    #[arg(long, value_parser, default_value = "false")]
+    pub json_pretty: bool,
+
+    /// Output header and rows as a yaml document, write into <DIR>/<name of binary file>.yaml if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_yaml: String,
+
+    /// Output header and rows as lua table like xresloader, write into <DIR>/<name of binary file>.lua if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_lua: String,
+
+    /// Index rows of --output-lua by this field instead of a list
+    #[arg(long, value_parser, default_value = "", value_name = "FIELD NAME")]
+    pub lua_key_field: String,
+
+    /// Pretty mode of --output-lua
+    #[arg(long, value_parser, default_value = "false")]
+    pub lua_pretty: bool,
+
+    /// Output header and rows as msgpack, write into <DIR>/<name of binary file>.msgpack if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_msgpack: String,
+
+    /// Encode messages of --output-msgpack as arrays of all fields in declaration order instead of maps
+    #[arg(long, value_parser, default_value = "false")]
+    pub msgpack_message_as_array: bool,
+
+    /// Encode enums of --output-msgpack by name instead of number
+    #[arg(long, value_parser, default_value = "false")]
+    pub msgpack_enum_as_name: bool,
+
+    /// Output rows as csv of UE DataTable, write into <DIR>/<name of binary file>.csv if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_ue_csv: String,
+
+    /// Output rows as json of UE DataTable, write into <DIR>/<name of binary file>.json if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE OR DIRECTORY"
+    )]
+    pub output_ue_json: String,
+
+    /// Output rows as json lines while decoding, write into <DIR>/<name of binary file>.jsonl if it's a directory or stdout if it's -
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT FILE, DIRECTORY OR -"
+    )]
+    pub output_ndjson: String,
+
+    /// Old binary file of diff mode
+    #[arg(long, value_parser, default_value = "", value_name = "OLD BINARY FILE")]
+    pub diff_old_bin: String,
+
+    /// New binary file of diff mode
+    #[arg(long, value_parser, default_value = "", value_name = "NEW BINARY FILE")]
+    pub diff_new_bin: String,
+
+    /// Fields used to match rows in diff mode(can be used mulpitle times or split by ',')
+    #[arg(long, value_parser, action = ArgAction::Append, value_name = "FIELD NAME")]
+    pub diff_key_field: Vec<String>,
+
+    /// Use fields with this org.xresloader.field_unique_tag to match rows in diff mode
+    #[arg(long, value_parser, default_value = "", value_name = "UNIQUE TAG")]
+    pub diff_unique_tag: String,
+
+    /// Output diff result as json
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT JSON FILE PATH"
+    )]
+    pub output_diff_json: String,
+
+    /// Output diff result as text lines
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT TEXT FILE PATH"
+    )]
+    pub output_diff_text: String,
+
+    /// Input file of encode mode, json document or array of rows(json), json lines(ndjson) or text format rows separated by lines starting with ---
+    #[arg(long, value_parser, default_value = "", value_name = "INPUT FILE")]
+    pub encode_input: String,
+
+    /// Output binary file of encode mode
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT BINARY FILE"
+    )]
+    pub encode_output: String,
+
+    /// Format of --encode-input, auto(by extension), json, ndjson or text
+    #[arg(
+        long,
+        value_parser,
+        default_value = "auto",
+        value_name = "auto|json|ndjson|text"
+    )]
+    pub encode_format: String,
+
+    /// Message type of rows in encode mode, it's required if it's not in --encode-input
+    #[arg(long, value_parser, default_value = "", value_name = "MESSAGE TYPE")]
+    pub encode_message_type: String,
+
+    /// xres_ver in header of encode mode, use the value in --encode-input or version of this tool if not set
+    #[arg(long, value_parser, default_value = "", value_name = "VERSION")]
+    pub encode_xres_ver: String,
+
+    /// data_ver in header of encode mode, use the value in --encode-input or 1.0.0.0 if not set
+    #[arg(long, value_parser, default_value = "", value_name = "VERSION")]
+    pub encode_data_ver: String,
+
+    /// description in header of encode mode, use the value in --encode-input if not set
+    #[arg(long, value_parser, default_value = "", value_name = "DESCRIPTION")]
+    pub encode_description: String,
+
+    /// Hash algorithm of hash_code in header of encode mode(md5, sha1, sha224, sha256, sha384, sha512)
+    #[arg(long, value_parser, default_value = "sha256", value_name = "ALGORITHM")]
+    pub encode_hash_algorithm: String,
+
+    /// Binary files to localize or pseudo-localize(can be used mulpitle times), they are not dumped like -b
+    #[arg(long, value_parser, action = ArgAction::Append, value_name = "BINARY FILE")]
+    pub localize_bin: Vec<String>,
+
+    /// Translations of localize mode, string table json with translation of each string, csv with source and translation columns, or PO file
+    #[arg(long, value_parser, default_value = "", value_name = "INPUT FILE")]
+    pub localize_input: String,
+
+    /// Format of --localize-input, auto(by extension), json, csv or po
+    #[arg(
+        long,
+        value_parser,
+        default_value = "auto",
+        value_name = "auto|json|csv|po"
+    )]
+    pub localize_format: String,
+
+    /// Output localized binary file, write into <DIR>/<name of binary file>.bin if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT BINARY FILE OR DIRECTORY"
+    )]
+    pub localize_output: String,
+
+    /// data_ver in header of localized and pseudo-localized binary files, keep the original one if not set
+    #[arg(long, value_parser, default_value = "", value_name = "VERSION")]
+    pub localize_data_ver: String,
+
+    /// Hash algorithm of hash_code in header of localized and pseudo-localized binary files, use the algorithm of original hash_code if not set
+    #[arg(long, value_parser, default_value = "", value_name = "ALGORITHM")]
+    pub localize_hash_algorithm: String,
+
+    /// Output pseudo-localized binary file, write into <DIR>/<name of binary file>.bin if it's a directory
+    #[arg(
+        long,
+        value_parser,
+        default_value = "",
+        value_name = "OUTPUT BINARY FILE OR DIRECTORY"
+    )]
+    pub pseudo_localize_output: String,
+
+    /// Percent of length expansion of pseudo-localized strings, placeholders are not counted
+    #[arg(long, value_parser, default_value = "30", value_name = "PERCENT")]
+    pub pseudo_localize_expansion: u32,
+
+    /// Marker inserted before pseudo-localized strings
+    #[arg(long, value_parser, default_value = "[", value_name = "MARKER")]
+    pub pseudo_localize_prefix: String,
+
+    /// Marker appended after pseudo-localized strings
+    #[arg(long, value_parser, default_value = "]", value_name = "MARKER")]
+    pub pseudo_localize_suffix: String,
+
+    /// Keep characters of pseudo-localized strings, only add markers and expansion
+    #[arg(long, value_parser, default_value = "false")]
+    pub pseudo_localize_without_accents: bool,
 }
@@ -25,6 +25,7 @@ pub struct DumpPluginSheetDataSource {
 pub struct DumpPluginBlockDataSource {
     pub xres_ver: ::std::string::String,
     pub data_ver: ::std::string::String,
+    pub data_message_type: ::std::string::String,
     pub file_path: ::std::string::String,
     pub count: u32,
     pub hash_code: ::std::string::String,
@@ -67,6 +68,7 @@ impl DumpPluginBlockDataSource {
         Rc::new(DumpPluginBlockDataSource {
             xres_ver: data_blocks.header.xres_ver.clone(),
             data_ver: data_blocks.header.data_ver.clone(),
+            data_message_type: data_blocks.data_message_type.clone(),
             file_path,
             count: data_blocks.header.count,
             hash_code: data_blocks.header.hash_code.clone(),
@@ -80,6 +82,44 @@ impl DumpPluginBlockDataSource {
             },
         })
     }
+
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut json_item_head = json::JsonValue::new_object();
+
+        let _ = json_item_head.insert("xres_ver", self.xres_ver.clone());
+        let _ = json_item_head.insert("data_ver", self.data_ver.clone());
+        let _ = json_item_head.insert("data_message_type", self.data_message_type.clone());
+        let _ = json_item_head.insert("file_path", self.file_path.clone());
+        let _ = json_item_head.insert("count", self.count);
+        let _ = json_item_head.insert("hash_code", self.hash_code.clone());
+        let _ = json_item_head.insert("description", self.description.clone());
+        let _ = json_item_head.insert("data_source", {
+            let mut ds = json::JsonValue::new_array();
+            utility::for_each_ordered_vec_by(
+                &self.data_source,
+                |a, b| {
+                    if a.item.file == b.item.file {
+                        a.item.sheet.cmp(&b.item.sheet)
+                    } else {
+                        a.item.file.cmp(&b.item.file)
+                    }
+                },
+                |source| {
+                    let mut d = json::JsonValue::new_object();
+                    let _ = d.insert("file", source.item.file.clone());
+                    let _ = d.insert("sheet", source.item.sheet.clone());
+                    if source.count > 0 {
+                        let _ = d.insert("count", source.count);
+                    }
+                    let _ = ds.push(d);
+                },
+            );
+
+            ds
+        });
+
+        json_item_head
+    }
 }
 
 pub trait DumpPluginBlockInterface: Any {
@@ -106,45 +146,18 @@ pub trait DumpPluginInterface {
         block: &mut Box<dyn DumpPluginBlockInterface>,
         message: &dyn MessageDyn,
         data_source: &DumpPluginSheetDataSource,
+        row_index: usize,
     );
 
     fn push_block(&mut self, block: Box<dyn DumpPluginBlockInterface>);
 
-    fn header_to_json(&self, head: &DumpPluginBlockDataSource) -> json::JsonValue {
-        let mut json_item_head = json::JsonValue::new_object();
-
-        let _ = json_item_head.insert("xres_ver", head.xres_ver.clone());
-        let _ = json_item_head.insert("data_ver", head.data_ver.clone());
-        let _ = json_item_head.insert("file_path", head.file_path.clone());
-        let _ = json_item_head.insert("count", head.count);
-        let _ = json_item_head.insert("hash_code", head.hash_code.clone());
-        let _ = json_item_head.insert("description", head.description.clone());
-        let _ = json_item_head.insert("data_source", {
-            let mut ds = json::JsonValue::new_array();
-            utility::for_each_ordered_vec_by(
-                &head.data_source,
-                |a, b| {
-                    if a.item.file == b.item.file {
-                        a.item.sheet.cmp(&b.item.sheet)
-                    } else {
-                        a.item.file.cmp(&b.item.file)
-                    }
-                },
-                |source| {
-                    let mut d = json::JsonValue::new_object();
-                    let _ = d.insert("file", source.item.file.clone());
-                    let _ = d.insert("sheet", source.item.sheet.clone());
-                    if source.count > 0 {
-                        let _ = d.insert("count", source.count);
-                    }
-                    let _ = ds.push(d);
-                },
-            );
-
-            ds
-        });
+    /// Row outputs receive rows with only the fields selected by --select
+    fn is_row_output(&self) -> bool {
+        false
+    }
 
-        json_item_head
+    fn header_to_json(&self, head: &DumpPluginBlockDataSource) -> json::JsonValue {
+        head.to_json()
     }
 
     fn to_json(&self) -> Vec<json::JsonValue>;
@@ -0,0 +1,69 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use xresloader_protocol::proto::pb_header_v3::Xresloader_datablocks;
+
+fn digest_data_block<D: Digest>(data_block: &[Vec<u8>]) -> String {
+    let mut hasher = D::new();
+    for block in data_block {
+        hasher.update(block);
+    }
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Calculate digest of all data blocks with algorithm(md5, sha1, sha224, sha256, sha384, sha512)
+/// and return it in the format of `<algorithm>:<hex digest>`, which is the same as `hash_code` in header.
+pub fn calculate_hash_code(algorithm: &str, data_block: &[Vec<u8>]) -> Result<String, String> {
+    let normalized_algorithm = algorithm.trim().to_lowercase().replace('-', "");
+    let digest = match normalized_algorithm.as_str() {
+        "md5" => digest_data_block::<Md5>(data_block),
+        "sha1" => digest_data_block::<Sha1>(data_block),
+        "sha224" => digest_data_block::<Sha224>(data_block),
+        "sha256" => digest_data_block::<Sha256>(data_block),
+        "sha384" => digest_data_block::<Sha384>(data_block),
+        "sha512" => digest_data_block::<Sha512>(data_block),
+        _ => {
+            return Err(format!("unsupported hash algorithm \"{}\"", algorithm));
+        }
+    };
+
+    Ok(format!("{}:{}", normalized_algorithm, digest))
+}
+
+pub fn verify_hash_code(data_blocks: &Xresloader_datablocks) -> Result<(), String> {
+    let hash_code = data_blocks.header.hash_code.trim();
+    if hash_code.is_empty() {
+        return Err("there is no hash code in header".to_string());
+    }
+
+    let (algorithm, expect_digest) = match hash_code.split_once(':') {
+        Some(x) => x,
+        None => {
+            return Err(format!(
+                "invalid hash code \"{}\", it should be <algorithm>:<digest>",
+                hash_code
+            ));
+        }
+    };
+
+    let real_hash_code = calculate_hash_code(algorithm, &data_blocks.data_block)?;
+    let real_digest = match real_hash_code.split_once(':') {
+        Some((_, x)) => x,
+        None => real_hash_code.as_str(),
+    };
+
+    if !real_digest.eq_ignore_ascii_case(expect_digest.trim()) {
+        return Err(format!(
+            "hash code mismatch, expect {}, real {}",
+            hash_code, real_hash_code
+        ));
+    }
+
+    Ok(())
+}
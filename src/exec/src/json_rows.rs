@@ -0,0 +1,215 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::ordered_generator;
+use super::utility;
+use super::yaml_generator;
+
+use protobuf::MessageDyn;
+
+struct JsonRowsContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    pub rows: json::JsonValue,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for JsonRowsContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl JsonRowsContent {
+    pub fn to_json(&self, json_item_head: json::JsonValue) -> json::JsonValue {
+        let mut json_item = json::JsonValue::new_object();
+        let _ = json_item.insert("header", json_item_head);
+        let _ = json_item.insert("rows", self.rows.clone());
+        json_item
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JsonRowsFormat {
+    Json,
+    Yaml,
+}
+
+/// Header and rows converted by json mapping of protobuf, written as json or yaml documents
+pub struct DumpPluginJsonRows {
+    content: VecDeque<Box<JsonRowsContent>>,
+    has_error: bool,
+
+    // output
+    format: JsonRowsFormat,
+    pretty: bool,
+    write_to_path: String,
+}
+
+impl DumpPluginJsonRows {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_json.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(&args.output_json, "--output-json", args.bin_file.len()) {
+            return (None, true);
+        }
+
+        (
+            Some(Box::new(DumpPluginJsonRows {
+                content: VecDeque::new(),
+                has_error: false,
+                format: JsonRowsFormat::Json,
+                pretty: args.json_pretty,
+                write_to_path: args.output_json.clone(),
+            })),
+            false,
+        )
+    }
+
+    pub fn build_yaml(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_yaml.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(&args.output_yaml, "--output-yaml", args.bin_file.len()) {
+            return (None, true);
+        }
+
+        (
+            Some(Box::new(DumpPluginJsonRows {
+                content: VecDeque::new(),
+                has_error: false,
+                format: JsonRowsFormat::Yaml,
+                pretty: true,
+                write_to_path: args.output_yaml.clone(),
+            })),
+            false,
+        )
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginJsonRows {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(JsonRowsContent {
+            head: data_source,
+            rows: json::JsonValue::new_array(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        _data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<JsonRowsContent>() {
+            match protobuf_json_mapping::print_to_string(message)
+                .map_err(|e| e.to_string())
+                .and_then(|x| json::parse(&x).map_err(|e| e.to_string()))
+            {
+                Ok(x) => {
+                    let _ = rb.rows.push(x);
+                }
+                Err(e) => {
+                    error!(
+                        "Convert row {} of file {} to json failed, {}",
+                        row_index, rb.head.file_path, e
+                    );
+                    self.has_error = true;
+                }
+            }
+        } else {
+            error!(
+                "In DumpPluginJsonRows::load_message, the block is not JsonRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(rb) = block.into_any().downcast::<JsonRowsContent>() {
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginJsonRows::push_block, the block is not JsonRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn is_row_output(&self) -> bool {
+        true
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        let mut ret = Vec::with_capacity(self.content.len());
+        for json_rows in &self.content {
+            ret.push(json_rows.to_json(self.header_to_json(json_rows.head.as_ref())));
+        }
+        ret
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        let mut ret = Vec::with_capacity(self.content.len());
+        for json_rows in &self.content {
+            let json_item = json_rows.to_json(self.header_to_json(json_rows.head.as_ref()));
+            if self.format == JsonRowsFormat::Yaml {
+                ret.push(yaml_generator::stringify(&json_item));
+            } else if self.pretty {
+                ret.push(ordered_generator::stringify_pretty(json_item, 2));
+            } else {
+                ret.push(ordered_generator::stringify(json_item));
+            }
+        }
+        ret
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = if self.has_error { Err(()) } else { Ok(()) };
+        for (json_rows, text) in self.content.iter().zip(self.to_text()) {
+            let output_file = utility::get_output_file_path(
+                &self.write_to_path,
+                &json_rows.head.file_path,
+                match self.format {
+                    JsonRowsFormat::Json => "json",
+                    JsonRowsFormat::Yaml => "yaml",
+                },
+            );
+            match utility::create_output_file(&output_file) {
+                Ok(mut f) => {
+                    if let Err(e) = f.write_all(text.as_bytes()) {
+                        error!("Try to write rows to {} failed, {}", output_file, e);
+                        ret = Err(());
+                    }
+                }
+                Err(e) => {
+                    error!("Try to open {} to write rows failed, {}", output_file, e);
+                    ret = Err(());
+                }
+            }
+        }
+
+        ret
+    }
+}
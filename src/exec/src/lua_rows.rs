@@ -0,0 +1,507 @@
+use std::any::Any;
+use std::collections::{HashSet, VecDeque};
+use std::io::Write;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_projection::RowProjection;
+use super::utility;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{FieldDescriptor, ReflectFieldRef, ReflectValueRef, RuntimeFieldType};
+
+const LUA_KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+const INDENT_SPACES: usize = 4;
+
+struct LuaRow {
+    /// Lua key of row when rows are indexed by --lua-key-field
+    pub key: Option<String>,
+    pub value: String,
+}
+
+struct LuaRowsContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    pub message_name: String,
+    pub key_field: Option<FieldDescriptor>,
+    pub keys: HashSet<String>,
+    pub rows: Vec<LuaRow>,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for LuaRowsContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+fn is_lua_identifier(input: &str) -> bool {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !LUA_KEYWORDS.contains(&input)
+}
+
+fn escape_lua_string(input: &[u8]) -> String {
+    let mut ret = String::with_capacity(input.len() + 2);
+    ret.push('"');
+    for c in String::from_utf8_lossy(input).chars() {
+        match c {
+            '\\' => ret.push_str("\\\\"),
+            '"' => ret.push_str("\\\""),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+                ret.push_str(&format!("\\{:03}", c as u32));
+            }
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Bytes may be not valid UTF-8, all non-printable bytes are escaped
+fn escape_lua_bytes(input: &[u8]) -> String {
+    if std::str::from_utf8(input).is_ok() {
+        return escape_lua_string(input);
+    }
+
+    let mut ret = String::with_capacity(input.len() + 2);
+    ret.push('"');
+    for b in input {
+        match b {
+            b'\\' => ret.push_str("\\\\"),
+            b'"' => ret.push_str("\\\""),
+            0x20..=0x7e => ret.push(*b as char),
+            _ => ret.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+fn lua_key(name: &str) -> String {
+    if is_lua_identifier(name) {
+        name.to_string()
+    } else {
+        format!("[{}]", escape_lua_string(name.as_bytes()))
+    }
+}
+
+fn lua_float(x: f64) -> String {
+    if x.is_nan() {
+        "0/0".to_string()
+    } else if x.is_infinite() {
+        if x > 0.0 {
+            "math.huge".to_string()
+        } else {
+            "-math.huge".to_string()
+        }
+    } else {
+        format!("{:?}", x)
+    }
+}
+
+/// Render scalar as Lua value, enums are written as numbers like xresloader
+fn lua_scalar(value: &ReflectValueRef) -> String {
+    match value {
+        ReflectValueRef::U32(x) => x.to_string(),
+        ReflectValueRef::U64(x) => x.to_string(),
+        ReflectValueRef::I32(x) => x.to_string(),
+        ReflectValueRef::I64(x) => x.to_string(),
+        ReflectValueRef::F32(x) => lua_float(*x as f64),
+        ReflectValueRef::F64(x) => lua_float(*x),
+        ReflectValueRef::Bool(x) => x.to_string(),
+        ReflectValueRef::String(x) => escape_lua_string(x.as_bytes()),
+        ReflectValueRef::Bytes(x) => escape_lua_bytes(x),
+        ReflectValueRef::Enum(_, x) => x.to_string(),
+        ReflectValueRef::Message(_) => "{}".to_string(),
+    }
+}
+
+/// Key of map or indexed rows
+fn lua_table_key(value: &ReflectValueRef) -> String {
+    match value {
+        ReflectValueRef::String(x) => lua_key(x),
+        _ => format!("[{}]", lua_scalar(value)),
+    }
+}
+
+struct LuaWriter {
+    pretty: bool,
+    out: String,
+}
+
+impl LuaWriter {
+    fn new(pretty: bool) -> Self {
+        LuaWriter {
+            pretty,
+            out: String::new(),
+        }
+    }
+
+    fn new_line(&mut self, depth: usize) {
+        if self.pretty {
+            self.out.push('\n');
+            for _ in 0..depth * INDENT_SPACES {
+                self.out.push(' ');
+            }
+        }
+    }
+
+    fn write_entries(&mut self, entries: Vec<(Option<String>, String)>, depth: usize) {
+        if entries.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        let entry_count = entries.len();
+        for (index, (key, value)) in entries.into_iter().enumerate() {
+            self.new_line(depth + 1);
+            if let Some(k) = key {
+                self.out.push_str(&k);
+                self.out.push_str(if self.pretty { " = " } else { "=" });
+            }
+            self.out.push_str(&value);
+            if index + 1 < entry_count || self.pretty {
+                self.out.push(',');
+            }
+        }
+        self.new_line(depth);
+        self.out.push('}');
+    }
+
+    fn render_value(&self, value: &ReflectValueRef, depth: usize) -> String {
+        match value {
+            ReflectValueRef::Message(m) => {
+                let mut writer = LuaWriter::new(self.pretty);
+                writer.write_message(m.deref(), depth);
+                writer.out
+            }
+            _ => lua_scalar(value),
+        }
+    }
+
+    fn write_message(&mut self, message: &dyn MessageDyn, depth: usize) {
+        let mut entries = Vec::new();
+        for field in message.descriptor_dyn().fields() {
+            let value = match field.get_reflect(message) {
+                // Scalars with default value of proto3 are not set, like json mapping
+                ReflectFieldRef::Optional(v) => match v.value() {
+                    Some(x) => self.render_value(&x, depth + 1),
+                    None => continue,
+                },
+                ReflectFieldRef::Repeated(v) => {
+                    if v.is_empty() {
+                        continue;
+                    }
+                    let mut writer = LuaWriter::new(self.pretty);
+                    writer.write_entries(
+                        v.into_iter()
+                            .map(|x| (None, self.render_value(&x, depth + 2)))
+                            .collect(),
+                        depth + 1,
+                    );
+                    writer.out
+                }
+                ReflectFieldRef::Map(v) => {
+                    if v.is_empty() {
+                        continue;
+                    }
+                    let mut map_entries: Vec<(Option<String>, String)> = (&v)
+                        .into_iter()
+                        .map(|(k, x)| (Some(lua_table_key(&k)), self.render_value(&x, depth + 2)))
+                        .collect();
+                    // Keep stable order of map
+                    map_entries.sort_by(|a, b| a.0.cmp(&b.0));
+                    let mut writer = LuaWriter::new(self.pretty);
+                    writer.write_entries(map_entries, depth + 1);
+                    writer.out
+                }
+            };
+
+            entries.push((Some(lua_key(field.name())), value));
+        }
+
+        self.write_entries(entries, depth);
+    }
+}
+
+impl LuaRowsContent {
+    fn header_to_lua(&self, pretty: bool, depth: usize) -> String {
+        let mut data_source_entries = Vec::new();
+        for source in &self.head.data_source {
+            let mut entries = vec![
+                (
+                    Some("file".to_string()),
+                    escape_lua_string(source.item.file.as_bytes()),
+                ),
+                (
+                    Some("sheet".to_string()),
+                    escape_lua_string(source.item.sheet.as_bytes()),
+                ),
+            ];
+            if source.count > 0 {
+                entries.push((Some("count".to_string()), source.count.to_string()));
+            }
+            let mut writer = LuaWriter::new(pretty);
+            writer.write_entries(entries, depth + 2);
+            data_source_entries.push((None, writer.out));
+        }
+
+        let mut data_source_writer = LuaWriter::new(pretty);
+        data_source_writer.write_entries(data_source_entries, depth + 1);
+
+        let mut writer = LuaWriter::new(pretty);
+        writer.write_entries(
+            vec![
+                (
+                    Some("xres_ver".to_string()),
+                    escape_lua_string(self.head.xres_ver.as_bytes()),
+                ),
+                (
+                    Some("data_ver".to_string()),
+                    escape_lua_string(self.head.data_ver.as_bytes()),
+                ),
+                (Some("count".to_string()), self.head.count.to_string()),
+                (
+                    Some("hash_code".to_string()),
+                    escape_lua_string(self.head.hash_code.as_bytes()),
+                ),
+                (
+                    Some("description".to_string()),
+                    escape_lua_string(self.head.description.as_bytes()),
+                ),
+                (Some("data_source".to_string()), data_source_writer.out),
+            ],
+            depth,
+        );
+        writer.out
+    }
+
+    /// The same layout as xresloader: `return { [1] = header, <message name> = rows }`
+    pub fn to_lua(&self, pretty: bool) -> String {
+        let mut rows_writer = LuaWriter::new(pretty);
+        rows_writer.write_entries(
+            self.rows
+                .iter()
+                .map(|x| (x.key.clone(), x.value.clone()))
+                .collect(),
+            1,
+        );
+
+        let mut writer = LuaWriter::new(pretty);
+        writer.write_entries(
+            vec![
+                (Some("[1]".to_string()), self.header_to_lua(pretty, 1)),
+                (Some(lua_key(&self.message_name)), rows_writer.out),
+            ],
+            0,
+        );
+
+        format!(
+            "-- this file is generated by xresloader-dump-bin from {}, please don't edit it.\n\nreturn {}\n",
+            self.head.file_path, writer.out
+        )
+    }
+}
+
+pub struct DumpPluginLuaRows {
+    content: VecDeque<Box<LuaRowsContent>>,
+    key_field: String,
+    /// Rows are received without projection, so --lua-key-field can be read even if it's not selected
+    projection: Option<RowProjection>,
+    has_error: bool,
+
+    // output
+    pretty: bool,
+    write_to_path: String,
+}
+
+impl DumpPluginLuaRows {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_lua.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(&args.output_lua, "--output-lua", args.bin_file.len()) {
+            return (None, true);
+        }
+
+        let projection = match RowProjection::build(&args.select) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Parse --select failed, {}", e);
+                return (None, true);
+            }
+        };
+
+        (
+            Some(Box::new(DumpPluginLuaRows {
+                content: VecDeque::new(),
+                key_field: args.lua_key_field.trim().to_string(),
+                projection,
+                has_error: false,
+                pretty: args.lua_pretty,
+                write_to_path: args.output_lua.clone(),
+            })),
+            false,
+        )
+    }
+
+    fn find_key_field(
+        &mut self,
+        message: &dyn MessageDyn,
+        file_path: &str,
+    ) -> Option<FieldDescriptor> {
+        let message_desc = message.descriptor_dyn();
+        match utility::find_message_field(&message_desc, &self.key_field) {
+            Some(x) if matches!(x.runtime_field_type(), RuntimeFieldType::Singular(_)) => Some(x),
+            Some(_) => {
+                error!(
+                    "--lua-key-field {} of {} in file {} is not a singular field, rows will be written as a list",
+                    self.key_field,
+                    message_desc.full_name(),
+                    file_path
+                );
+                self.has_error = true;
+                None
+            }
+            None => {
+                error!(
+                    "--lua-key-field {} is not found in {} of file {}, rows will be written as a list",
+                    self.key_field,
+                    message_desc.full_name(),
+                    file_path
+                );
+                self.has_error = true;
+                None
+            }
+        }
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginLuaRows {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(LuaRowsContent {
+            head: data_source,
+            message_name: String::new(),
+            key_field: None,
+            keys: HashSet::new(),
+            rows: Vec::new(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        _data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<LuaRowsContent>() {
+            if rb.message_name.is_empty() {
+                rb.message_name = message.descriptor_dyn().name().to_string();
+                if !self.key_field.is_empty() {
+                    rb.key_field = self.find_key_field(message, &rb.head.file_path);
+                }
+            }
+
+            let key = match &rb.key_field {
+                Some(field) => {
+                    let key = lua_table_key(&field.get_singular_field_or_default(message));
+                    if !rb.keys.insert(key.clone()) {
+                        warn!(
+                            "Row {} of file {} has duplicated key {} = {}, it will override the previous one in lua",
+                            row_index,
+                            rb.head.file_path,
+                            field.name(),
+                            utility::pb_value_to_string(
+                                &field.get_singular_field_or_default(message)
+                            )
+                        );
+                    }
+                    Some(key)
+                }
+                None => None,
+            };
+
+            let selected_message = self.projection.as_ref().map(|x| x.project(message));
+            let mut writer = LuaWriter::new(self.pretty);
+            writer.write_message(selected_message.as_deref().unwrap_or(message), 2);
+            rb.rows.push(LuaRow {
+                key,
+                value: writer.out,
+            });
+        } else {
+            error!(
+                "In DumpPluginLuaRows::load_message, the block is not LuaRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(rb) = block.into_any().downcast::<LuaRowsContent>() {
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginLuaRows::push_block, the block is not LuaRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        Vec::new()
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        self.content.iter().map(|x| x.to_lua(self.pretty)).collect()
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = if self.has_error { Err(()) } else { Ok(()) };
+        for lua_rows in &self.content {
+            let output_file =
+                utility::get_output_file_path(&self.write_to_path, &lua_rows.head.file_path, "lua");
+            match utility::create_output_file(&output_file) {
+                Ok(mut f) => {
+                    if let Err(e) = f.write_all(lua_rows.to_lua(self.pretty).as_bytes()) {
+                        error!("Try to write lua to {} failed, {}", output_file, e);
+                        ret = Err(());
+                    }
+                }
+                Err(e) => {
+                    error!("Try to open {} to write lua failed, {}", output_file, e);
+                    ret = Err(());
+                }
+            }
+        }
+
+        ret
+    }
+}
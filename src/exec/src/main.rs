@@ -23,20 +23,43 @@ use std::rc::Rc;
 use protobuf::{Message, MessageFull, descriptor::FileDescriptorSet};
 // use xresloader_protocol::proto::Xresloader_datablocks;
 
+mod bin_diff;
+mod bin_encode;
+mod bin_localize;
+mod csv_table;
+mod data_check;
+mod data_check_list_size;
+mod data_check_required;
+mod data_check_unique_tag;
+mod data_check_validator;
 mod dump_options;
 mod dump_plugin;
 mod file_descriptor_index;
+mod hash_code;
+mod json_rows;
 mod logger;
+mod lua_rows;
+mod msgpack_rows;
+mod ndjson_rows;
 mod ordered_generator;
+mod row_filter;
+mod row_flatten;
+mod row_key;
+mod row_projection;
 mod string_table;
 mod tagged_field;
+mod ue_data_table;
 mod utility;
+mod xlsx_table;
+mod xresloader_bin;
+mod yaml_generator;
 
 type DumpOptions = dump_options::DumpOptions;
 use file_descriptor_index::FileDescriptorIndex;
 
 fn build_dump_plugins(
     args: &DumpOptions,
+    desc_index: &FileDescriptorIndex,
 ) -> (Vec<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
     let mut ret = Vec::with_capacity(8);
     let mut has_error = false;
@@ -44,9 +67,19 @@ fn build_dump_plugins(
     let new_plugin_fns = [
         tagged_field::DumpPluginTaggedField::build,
         string_table::DumpPluginStringTable::build,
+        data_check::DumpPluginDataCheck::build,
+        csv_table::DumpPluginCsvTable::build,
+        xlsx_table::DumpPluginXlsxTable::build,
+        json_rows::DumpPluginJsonRows::build,
+        json_rows::DumpPluginJsonRows::build_yaml,
+        ndjson_rows::DumpPluginNdjsonRows::build,
+        lua_rows::DumpPluginLuaRows::build,
+        msgpack_rows::DumpPluginMsgpackRows::build,
+        ue_data_table::DumpPluginUeDataTable::build_csv,
+        ue_data_table::DumpPluginUeDataTable::build_json,
     ];
     for new_plugin_fn in &new_plugin_fns {
-        let (new_plugin_inst, new_plugin_has_error) = new_plugin_fn(args);
+        let (new_plugin_inst, new_plugin_has_error) = new_plugin_fn(args, desc_index);
         if let Some(p) = new_plugin_inst {
             ret.push(p);
         }
@@ -70,6 +103,36 @@ fn build_dump_plugin_blocks(
     ret
 }
 
+fn check_data_blocks_count(
+    data_blocks: &xresloader_protocol::proto::pb_header_v3::Xresloader_datablocks,
+    bin_file: &str,
+) -> bool {
+    let mut ret = true;
+    let data_block_count = data_blocks.data_block.len();
+    if data_blocks.header.count as usize != data_block_count {
+        error!(
+            "File {} has {} data block(s) but the count in header is {}",
+            bin_file, data_block_count, data_blocks.header.count
+        );
+        ret = false;
+    }
+
+    // Old versions of xresloader do not write count of data source
+    let data_source = &data_blocks.header.data_source;
+    if !data_source.is_empty() && data_source.iter().all(|x| x.count > 0) {
+        let data_source_count: usize = data_source.iter().map(|x| x.count as usize).sum();
+        if data_source_count != data_block_count {
+            error!(
+                "File {} has {} data block(s) but the sum of count in data source is {}",
+                bin_file, data_block_count, data_source_count
+            );
+            ret = false;
+        }
+    }
+
+    ret
+}
+
 fn main() {
     let args = DumpOptions::parse();
 
@@ -81,14 +144,12 @@ fn main() {
 
     let mut desc_index = FileDescriptorIndex::new();
 
-    let (mut dump_plugins, dump_plugins_has_error) = build_dump_plugins(&args);
-
-    for pb_file in args.pb_file {
+    for pb_file in &args.pb_file {
         debug!("Load pb file: {}", pb_file);
         match std::fs::OpenOptions::new()
             .read(true)
             .write(false)
-            .open(&pb_file)
+            .open(pb_file)
         {
             Ok(mut f) => {
                 let mut bin_data = Vec::new();
@@ -103,7 +164,7 @@ fn main() {
                                 pb_file_unit.message_type.len(),
                                 pb_file_unit.enum_type.len()
                             );
-                            desc_index.add_file(pb_file_unit, &pb_file);
+                            desc_index.add_file(pb_file_unit, pb_file);
                         }
                     }
                     Err(e) => {
@@ -120,8 +181,42 @@ fn main() {
         }
     }
 
+    let row_filter = match row_filter::RowFilter::build(&args.where_expr) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Parse --where failed, {}", e);
+            std::process::exit(1);
+        }
+    };
+    let row_projection = match row_projection::RowProjection::build(&args.select) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Parse --select failed, {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Some plugins need to find types in pb files
+    let (mut dump_plugins, dump_plugins_has_error) = build_dump_plugins(&args, &desc_index);
+
     let mut has_error = dump_plugins_has_error;
 
+    if !args.diff_old_bin.is_empty() || !args.diff_new_bin.is_empty() {
+        has_error |= bin_diff::run(&args, &mut desc_index);
+    }
+
+    if !args.encode_input.is_empty() || !args.encode_output.is_empty() {
+        has_error |= bin_encode::run(&args, &mut desc_index);
+    }
+
+    if !args.localize_input.is_empty() || !args.localize_output.is_empty() {
+        has_error |= bin_localize::run(&args, &mut desc_index);
+    }
+
+    if !args.pseudo_localize_output.is_empty() {
+        has_error |= bin_localize::run_pseudo_localize(&args, &mut desc_index);
+    }
+
     for ref bin_file in args.bin_file {
         debug!("Load xresloader output binary file: {}", bin_file);
         match std::fs::OpenOptions::new()
@@ -134,6 +229,20 @@ fn main() {
                 let _ = f.read_to_end(&mut bin_data);
                 match xresloader_protocol::proto::pb_header_v3::Xresloader_datablocks::parse_from_bytes(&bin_data) {
                     Ok(data_blocks) => {
+                        if args.verify_hash {
+                            match hash_code::verify_hash_code(&data_blocks) {
+                                Ok(_) => {
+                                    if !args.silence {
+                                        info!("Verify hash code of file {} success", &bin_file);
+                                    }
+                                }
+                                Err(e) => {
+                                    error!("Verify hash code of file {} failed, {}", &bin_file, e);
+                                    has_error = true;
+                                }
+                            }
+                        }
+
                         if data_blocks.data_message_type.is_empty() {
                             has_error = true;
                             error!("File {} has no data_message_type, please use xresloader 2.6 or upper", &bin_file);
@@ -153,6 +262,22 @@ fn main() {
                             }
                         };
 
+                        if let Some(filter) = &row_filter
+                            && let Err(e) = filter.validate(&message_descriptor)
+                        {
+                            error!("Expression of --where can not be used for file {}, {}", &bin_file, e);
+                            has_error = true;
+                            continue;
+                        }
+
+                        if let Some(projection) = &row_projection
+                            && let Err(e) = projection.validate(&message_descriptor)
+                        {
+                            error!("Field paths of --select can not be used for file {}, {}", &bin_file, e);
+                            has_error = true;
+                            continue;
+                        }
+
                         if !args.silence {
                             info!("======================== Header: {} ========================", &bin_file);
                             info!("xresloader version: {}", data_blocks.header.xres_ver);
@@ -172,6 +297,10 @@ fn main() {
                             }
                         }
 
+                        if !check_data_blocks_count(&data_blocks, bin_file) {
+                            has_error = true;
+                        }
+
                         let dump_plugin_block_data_source= dump_plugin::DumpPluginBlockDataSource::new(&data_blocks, bin_file.clone());
 
                         if !args.silence {
@@ -188,12 +317,22 @@ fn main() {
 
                         let mut current_data_source_idx = 0;
                         let mut current_data_source_left_row = 0;
+                        let mut current_data_source_exhausted = false;
+                        // Old versions of xresloader do not write count of data source, rows can not be attributed by count
+                        let has_data_source_count = !data_blocks.header.data_source.is_empty()
+                            && data_blocks.header.data_source.iter().all(|x| x.count > 0);
                         for row_data_block in &data_blocks.data_block {
                             row_index += 1;
-                            if current_data_source_left_row <= 0 && current_data_source_idx < data_blocks.header.data_source.len() {
-                                current_data_source_left_row = data_blocks.header.data_source[current_data_source_idx].count;
-                                fallback_dump_plugin_sheet_data_source = dump_plugin::DumpPluginSheetDataSource::new(&data_blocks.header.data_source[current_data_source_idx]);
-                                current_data_source_idx += 1;
+                            if current_data_source_left_row <= 0 {
+                                if current_data_source_idx < data_blocks.header.data_source.len() {
+                                    current_data_source_left_row = data_blocks.header.data_source[current_data_source_idx].count;
+                                    fallback_dump_plugin_sheet_data_source = dump_plugin::DumpPluginSheetDataSource::new(&data_blocks.header.data_source[current_data_source_idx]);
+                                    current_data_source_idx += 1;
+                                } else if has_data_source_count && !current_data_source_exhausted {
+                                    current_data_source_exhausted = true;
+                                    warn!("Data source in header of file {} runs out at row {}, the rest rows will be treated as from file: {}, sheet: {}",
+                                        &bin_file, row_index, fallback_dump_plugin_sheet_data_source.item.file, fallback_dump_plugin_sheet_data_source.item.sheet);
+                                }
                             }
                             if current_data_source_left_row > 0 {
                                 current_data_source_left_row -= 1;
@@ -201,12 +340,24 @@ fn main() {
 
                             match message_descriptor.parse_from_bytes(row_data_block) {
                                 Ok(message) => {
+                                    if let Some(filter) = &row_filter
+                                        && !filter.matches(message.as_ref())
+                                    {
+                                        continue;
+                                    }
+
+                                    let selected_message = row_projection.as_ref().map(|projection| projection.project(message.as_ref()));
                                     for i in 0 .. dump_plugins.len() {
                                         if let Some(ref mut block) = current_dump_plugin_blocks[i] {
+                                            let row_message = match &selected_message {
+                                                Some(x) if dump_plugins[i].is_row_output() => x.as_ref(),
+                                                _ => message.as_ref(),
+                                            };
                                             dump_plugins[i].load_message(
                                                 block,
-                                                message.as_ref(),
+                                                row_message,
                                                 &fallback_dump_plugin_sheet_data_source,
+                                                row_index,
                                             );
                                         }
                                     }
@@ -215,6 +366,11 @@ fn main() {
                                         continue;
                                     }
 
+                                    let message = match selected_message {
+                                        Some(x) => x,
+                                        None => message,
+                                    };
+
                                     if args.pretty {
                                         if args.plain {
                                             info!("  ------------ Row {} ------------\n{}", row_index, protobuf::text_format::print_to_string_pretty(message.as_ref()));
@@ -0,0 +1,318 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_flatten;
+use super::utility;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{
+    FieldDescriptor, ReflectFieldRef, ReflectValueRef, RuntimeFieldType, RuntimeType,
+};
+use rmp::encode;
+
+struct MsgpackRowsContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    /// Encoded rows, they are joined into an array when writing the document
+    pub rows: Vec<Vec<u8>>,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for MsgpackRowsContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+struct MsgpackEncoder {
+    /// Messages are encoded as arrays of all fields in declaration order, instead of maps of field names
+    message_as_array: bool,
+    enum_as_name: bool,
+}
+
+/// Unset message fields, oneof fields and optional fields are written as nil
+fn has_presence(field: &FieldDescriptor) -> bool {
+    matches!(
+        field.runtime_field_type(),
+        RuntimeFieldType::Singular(RuntimeType::Message(_))
+    ) || field.containing_oneof_including_synthetic().is_some()
+}
+
+impl MsgpackEncoder {
+    fn write_value(&self, out: &mut Vec<u8>, value: &ReflectValueRef) -> std::io::Result<()> {
+        match value {
+            ReflectValueRef::U32(x) => {
+                encode::write_uint(out, *x as u64)?;
+            }
+            ReflectValueRef::U64(x) => {
+                encode::write_uint(out, *x)?;
+            }
+            ReflectValueRef::I32(x) => {
+                encode::write_sint(out, *x as i64)?;
+            }
+            ReflectValueRef::I64(x) => {
+                encode::write_sint(out, *x)?;
+            }
+            ReflectValueRef::F32(x) => encode::write_f32(out, *x)?,
+            ReflectValueRef::F64(x) => encode::write_f64(out, *x)?,
+            ReflectValueRef::Bool(x) => encode::write_bool(out, *x)?,
+            ReflectValueRef::String(x) => encode::write_str(out, x)?,
+            ReflectValueRef::Bytes(x) => encode::write_bin(out, x)?,
+            ReflectValueRef::Enum(e, x) => match e.value_by_number(*x) {
+                Some(v) if self.enum_as_name => encode::write_str(out, v.name())?,
+                _ => {
+                    encode::write_sint(out, *x as i64)?;
+                }
+            },
+            ReflectValueRef::Message(m) => self.write_message(out, m.deref())?,
+        }
+
+        Ok(())
+    }
+
+    fn write_field(
+        &self,
+        out: &mut Vec<u8>,
+        message: &dyn MessageDyn,
+        field: &FieldDescriptor,
+    ) -> std::io::Result<()> {
+        match field.get_reflect(message) {
+            ReflectFieldRef::Optional(v) => match v.value() {
+                Some(x) => self.write_value(out, &x)?,
+                None if has_presence(field) => encode::write_nil(out)?,
+                None => self.write_value(out, &field.get_singular_field_or_default(message))?,
+            },
+            ReflectFieldRef::Repeated(v) => {
+                encode::write_array_len(out, v.len() as u32)?;
+                for x in v {
+                    self.write_value(out, &x)?;
+                }
+            }
+            ReflectFieldRef::Map(v) => {
+                encode::write_map_len(out, v.len() as u32)?;
+                // Keep stable order of map
+                let mut entries: Vec<(ReflectValueRef, ReflectValueRef)> =
+                    (&v).into_iter().collect();
+                entries.sort_by_key(|(k, _)| row_flatten::map_key_to_flatten_key(k));
+                for (k, x) in entries {
+                    self.write_value(out, &k)?;
+                    self.write_value(out, &x)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_message(&self, out: &mut Vec<u8>, message: &dyn MessageDyn) -> std::io::Result<()> {
+        let message_desc = message.descriptor_dyn();
+        if self.message_as_array {
+            let fields: Vec<FieldDescriptor> = message_desc.fields().collect();
+            encode::write_array_len(out, fields.len() as u32)?;
+            for field in &fields {
+                self.write_field(out, message, field)?;
+            }
+            return Ok(());
+        }
+
+        // Like json mapping, unset fields and empty repeated or map fields are not written
+        let fields: Vec<FieldDescriptor> = message_desc
+            .fields()
+            .filter(|field| match field.get_reflect(message) {
+                ReflectFieldRef::Optional(v) => v.value().is_some(),
+                ReflectFieldRef::Repeated(v) => !v.is_empty(),
+                ReflectFieldRef::Map(v) => !v.is_empty(),
+            })
+            .collect();
+        encode::write_map_len(out, fields.len() as u32)?;
+        for field in &fields {
+            encode::write_str(out, field.name())?;
+            self.write_field(out, message, field)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MsgpackRowsContent {
+    fn write_header(&self, out: &mut Vec<u8>) -> std::io::Result<()> {
+        encode::write_map_len(out, 8)?;
+        for (key, value) in [
+            ("xres_ver", &self.head.xres_ver),
+            ("data_ver", &self.head.data_ver),
+            ("data_message_type", &self.head.data_message_type),
+            ("file_path", &self.head.file_path),
+            ("hash_code", &self.head.hash_code),
+            ("description", &self.head.description),
+        ] {
+            encode::write_str(out, key)?;
+            encode::write_str(out, value)?;
+        }
+        encode::write_str(out, "count")?;
+        encode::write_uint(out, self.head.count as u64)?;
+
+        encode::write_str(out, "data_source")?;
+        encode::write_array_len(out, self.head.data_source.len() as u32)?;
+        for source in &self.head.data_source {
+            encode::write_map_len(out, if source.count > 0 { 3 } else { 2 })?;
+            encode::write_str(out, "file")?;
+            encode::write_str(out, &source.item.file)?;
+            encode::write_str(out, "sheet")?;
+            encode::write_str(out, &source.item.sheet)?;
+            if source.count > 0 {
+                encode::write_str(out, "count")?;
+                encode::write_sint(out, source.count as i64)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Document is a map of `header` and `rows`, like --output-json
+    pub fn to_msgpack(&self) -> std::io::Result<Vec<u8>> {
+        let mut ret = Vec::new();
+        encode::write_map_len(&mut ret, 2)?;
+        encode::write_str(&mut ret, "header")?;
+        self.write_header(&mut ret)?;
+        encode::write_str(&mut ret, "rows")?;
+        encode::write_array_len(&mut ret, self.rows.len() as u32)?;
+        for row in &self.rows {
+            ret.extend_from_slice(row);
+        }
+        Ok(ret)
+    }
+}
+
+pub struct DumpPluginMsgpackRows {
+    content: VecDeque<Box<MsgpackRowsContent>>,
+    encoder: MsgpackEncoder,
+    has_error: bool,
+
+    // output
+    write_to_path: String,
+}
+
+impl DumpPluginMsgpackRows {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_msgpack.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(
+            &args.output_msgpack,
+            "--output-msgpack",
+            args.bin_file.len(),
+        ) {
+            return (None, true);
+        }
+
+        (
+            Some(Box::new(DumpPluginMsgpackRows {
+                content: VecDeque::new(),
+                encoder: MsgpackEncoder {
+                    message_as_array: args.msgpack_message_as_array,
+                    enum_as_name: args.msgpack_enum_as_name,
+                },
+                has_error: false,
+                write_to_path: args.output_msgpack.clone(),
+            })),
+            false,
+        )
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginMsgpackRows {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(MsgpackRowsContent {
+            head: data_source,
+            rows: Vec::new(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        _data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<MsgpackRowsContent>() {
+            let mut row = Vec::new();
+            match self.encoder.write_message(&mut row, message) {
+                Ok(_) => rb.rows.push(row),
+                Err(e) => {
+                    error!(
+                        "Encode row {} of file {} to msgpack failed, {}",
+                        row_index, rb.head.file_path, e
+                    );
+                    self.has_error = true;
+                }
+            }
+        } else {
+            error!(
+                "In DumpPluginMsgpackRows::load_message, the block is not MsgpackRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(rb) = block.into_any().downcast::<MsgpackRowsContent>() {
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginMsgpackRows::push_block, the block is not MsgpackRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn is_row_output(&self) -> bool {
+        true
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        Vec::new()
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = if self.has_error { Err(()) } else { Ok(()) };
+        for msgpack_rows in &self.content {
+            let output_file = utility::get_output_file_path(
+                &self.write_to_path,
+                &msgpack_rows.head.file_path,
+                "msgpack",
+            );
+            let result = msgpack_rows.to_msgpack().and_then(|data| {
+                utility::create_output_file(&output_file).and_then(|mut f| f.write_all(&data))
+            });
+            if let Err(e) = result {
+                error!("Try to write msgpack to {} failed, {}", output_file, e);
+                ret = Err(());
+            }
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,209 @@
+use std::any::Any;
+use std::io::{BufWriter, Write};
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::ordered_generator;
+use super::utility;
+
+use protobuf::MessageDyn;
+
+type NdjsonWriter = BufWriter<Box<dyn Write>>;
+
+struct NdjsonRowsContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    /// Writer of this binary file when output into a directory
+    pub writer: Option<NdjsonWriter>,
+    pub output_file: String,
+    pub has_error: bool,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for NdjsonRowsContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Rows are written as soon as they are decoded, nothing is kept in memory
+pub struct DumpPluginNdjsonRows {
+    /// Writer shared by all binary files when output into one file or stdout
+    writer: Option<NdjsonWriter>,
+    has_error: bool,
+
+    // output
+    write_to_path: String,
+}
+
+fn open_writer(output_file: &str) -> std::io::Result<NdjsonWriter> {
+    if output_file == "-" {
+        return Ok(BufWriter::new(Box::new(std::io::stdout())));
+    }
+
+    let f = utility::create_output_file(output_file)?;
+    Ok(BufWriter::new(Box::new(f)))
+}
+
+impl DumpPluginNdjsonRows {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_ndjson.is_empty() {
+            return (None, false);
+        }
+
+        let writer = if utility::is_output_directory(&args.output_ndjson) {
+            None
+        } else {
+            match open_writer(&args.output_ndjson) {
+                Ok(x) => Some(x),
+                Err(e) => {
+                    error!(
+                        "Try to open {} to write json lines failed, {}",
+                        args.output_ndjson, e
+                    );
+                    return (None, true);
+                }
+            }
+        };
+
+        (
+            Some(Box::new(DumpPluginNdjsonRows {
+                writer,
+                has_error: false,
+                write_to_path: args.output_ndjson.clone(),
+            })),
+            false,
+        )
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginNdjsonRows {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        let mut ret = NdjsonRowsContent {
+            output_file: self.write_to_path.clone(),
+            head: data_source,
+            writer: None,
+            has_error: false,
+        };
+
+        if self.writer.is_none() {
+            ret.output_file =
+                utility::get_output_file_path(&self.write_to_path, &ret.head.file_path, "jsonl");
+            match open_writer(&ret.output_file) {
+                Ok(x) => ret.writer = Some(x),
+                Err(e) => {
+                    error!(
+                        "Try to open {} to write json lines failed, {}",
+                        ret.output_file, e
+                    );
+                    ret.has_error = true;
+                }
+            }
+        }
+
+        Some(Box::new(ret))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<NdjsonRowsContent>() {
+            let mut json_row = match protobuf_json_mapping::print_to_string(message)
+                .map_err(|e| e.to_string())
+                .and_then(|x| json::parse(&x).map_err(|e| e.to_string()))
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    error!(
+                        "Convert row {} of file {} to json failed, {}",
+                        row_index, rb.head.file_path, e
+                    );
+                    rb.has_error = true;
+                    return;
+                }
+            };
+            let _ = json_row.insert("_file", data_source.item.file.clone());
+            let _ = json_row.insert("_sheet", data_source.item.sheet.clone());
+            let _ = json_row.insert("_row", row_index);
+            let _ = json_row.insert(
+                "_message_type",
+                message.descriptor_dyn().full_name().to_string(),
+            );
+
+            let writer = match rb.writer.as_mut().or(self.writer.as_mut()) {
+                Some(x) => x,
+                None => return,
+            };
+            let line = ordered_generator::stringify(json_row);
+            if let Err(e) = writer
+                .write_all(line.as_bytes())
+                .and_then(|_| writer.write_all(b"\n"))
+            {
+                error!(
+                    "Try to write json lines to {} failed, {}",
+                    rb.output_file, e
+                );
+                rb.has_error = true;
+                // Stop writing rows of this file after the first failure
+                rb.writer = None;
+            }
+        } else {
+            error!(
+                "In DumpPluginNdjsonRows::load_message, the block is not NdjsonRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(mut rb) = block.into_any().downcast::<NdjsonRowsContent>() {
+            if let Some(writer) = rb.writer.as_mut().or(self.writer.as_mut())
+                && let Err(e) = writer.flush()
+            {
+                error!(
+                    "Try to write json lines to {} failed, {}",
+                    rb.output_file, e
+                );
+                rb.has_error = true;
+            }
+            self.has_error |= rb.has_error;
+        } else {
+            error!(
+                "In DumpPluginNdjsonRows::push_block, the block is not NdjsonRowsContent, ignore this message"
+            );
+        }
+    }
+
+    fn is_row_output(&self) -> bool {
+        true
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        Vec::new()
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        if self.has_error { Err(()) } else { Ok(()) }
+    }
+}
@@ -0,0 +1,890 @@
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{
+    MessageDescriptor, MessageRef, ReflectFieldRef, ReflectValueRef, RuntimeFieldType, RuntimeType,
+};
+
+use super::utility;
+
+#[derive(Clone, Debug, PartialEq)]
+enum WhereToken {
+    Ident(String),
+    String(String),
+    Int(i128),
+    Float(f64),
+    Operator(&'static str),
+}
+
+#[derive(Clone, Debug)]
+enum WhereLiteral {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(String),
+    /// Bare identifier, used as enum value name
+    Ident(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WhereCompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+enum WherePathSegment {
+    Field(String),
+    /// Index of repeated field or key of map field
+    Subscript(WhereLiteral),
+    /// All elements of repeated field or all values of map field
+    Wildcard,
+}
+
+/// Field path, an empty path(`_`) means the current value
+struct WherePath {
+    segments: Vec<WherePathSegment>,
+}
+
+enum WhereExpr {
+    Or(Box<WhereExpr>, Box<WhereExpr>),
+    And(Box<WhereExpr>, Box<WhereExpr>),
+    Not(Box<WhereExpr>),
+    Compare(WherePath, WhereCompareOp, WhereLiteral),
+    Match(WherePath, regex::Regex, bool),
+    Exists(WherePath),
+    Any(WherePath, Box<WhereExpr>),
+    All(WherePath, Box<WhereExpr>),
+}
+
+/// Type of the value a path points to, used to validate expressions before evaluating
+#[derive(Clone)]
+enum WhereValueType {
+    Message(MessageDescriptor),
+    Scalar(RuntimeType),
+}
+
+const WHERE_OPERATORS: [&str; 19] = [
+    "&&", "||", "==", "!=", "<=", ">=", "=~", "!~", "<", ">", "=", "!", "(", ")", "[", "]", ".",
+    ",", "*",
+];
+
+fn tokenize(input: &str) -> Result<Vec<WhereToken>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut ret = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut closed = false;
+            i += 1;
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' if i + 1 < chars.len() => {
+                        value.push(match chars[i + 1] {
+                            'n' => '\n',
+                            'r' => '\r',
+                            't' => '\t',
+                            x => x,
+                        });
+                        i += 2;
+                    }
+                    x if x == c => {
+                        closed = true;
+                        i += 1;
+                        break;
+                    }
+                    x => {
+                        value.push(x);
+                        i += 1;
+                    }
+                }
+            }
+            if !closed {
+                return Err(format!("unterminated string {}{}", c, value));
+            }
+            ret.push(WhereToken::String(value));
+            continue;
+        }
+
+        let is_negative_number = c == '-'
+            && i + 1 < chars.len()
+            && chars[i + 1].is_ascii_digit()
+            && !matches!(
+                ret.last(),
+                Some(WhereToken::Ident(_))
+                    | Some(WhereToken::Int(_))
+                    | Some(WhereToken::Float(_))
+                    | Some(WhereToken::String(_))
+                    | Some(WhereToken::Operator(")"))
+                    | Some(WhereToken::Operator("]"))
+            );
+        if c.is_ascii_digit() || is_negative_number {
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric()
+                    || chars[i] == '.'
+                    || ((chars[i] == '-' || chars[i] == '+')
+                        && (chars[i - 1] == 'e' || chars[i - 1] == 'E')))
+            {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            if let Ok(x) = literal.parse::<i128>() {
+                ret.push(WhereToken::Int(x));
+            } else if let Ok(x) = literal.parse::<f64>() {
+                ret.push(WhereToken::Float(x));
+            } else {
+                return Err(format!("invalid number {}", literal));
+            }
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            ret.push(WhereToken::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let rest: String = chars[i..std::cmp::min(i + 2, chars.len())].iter().collect();
+        match WHERE_OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            Some(op) => {
+                ret.push(WhereToken::Operator(op));
+                i += op.chars().count();
+            }
+            None => {
+                return Err(format!("unexpected character '{}'", c));
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+struct WhereParser {
+    tokens: Vec<WhereToken>,
+    position: usize,
+}
+
+impl WhereParser {
+    fn peek(&self) -> Option<&WhereToken> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&WhereToken> {
+        self.tokens.get(self.position + offset)
+    }
+
+    fn next(&mut self) -> Option<WhereToken> {
+        let ret = self.tokens.get(self.position).cloned();
+        // Always move forward, so the position can be moved back to report errors
+        self.position += 1;
+        ret
+    }
+
+    fn is_operator(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(WhereToken::Operator(x)) if *x == op)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(WhereToken::Ident(x)) if x == keyword)
+    }
+
+    fn expect_operator(&mut self, op: &str) -> Result<(), String> {
+        if self.is_operator(op) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("expect '{}' but got {}", op, self.describe_next()))
+        }
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(WhereToken::Ident(x)) => format!("'{}'", x),
+            Some(WhereToken::String(x)) => format!("\"{}\"", x),
+            Some(WhereToken::Int(x)) => format!("{}", x),
+            Some(WhereToken::Float(x)) => format!("{}", x),
+            Some(WhereToken::Operator(x)) => format!("'{}'", x),
+            None => "end of expression".to_string(),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<WhereExpr, String> {
+        let mut ret = self.parse_and()?;
+        while self.is_operator("||") || self.is_keyword("or") {
+            self.position += 1;
+            ret = WhereExpr::Or(Box::new(ret), Box::new(self.parse_and()?));
+        }
+        Ok(ret)
+    }
+
+    fn parse_and(&mut self) -> Result<WhereExpr, String> {
+        let mut ret = self.parse_not()?;
+        while self.is_operator("&&") || self.is_keyword("and") {
+            self.position += 1;
+            ret = WhereExpr::And(Box::new(ret), Box::new(self.parse_not()?));
+        }
+        Ok(ret)
+    }
+
+    fn parse_not(&mut self) -> Result<WhereExpr, String> {
+        if self.is_operator("!") || self.is_keyword("not") {
+            self.position += 1;
+            return Ok(WhereExpr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<WhereExpr, String> {
+        if self.is_operator("(") {
+            self.position += 1;
+            let ret = self.parse_or()?;
+            self.expect_operator(")")?;
+            return Ok(ret);
+        }
+
+        if (self.is_keyword("any") || self.is_keyword("all"))
+            && matches!(self.peek_at(1), Some(WhereToken::Operator("(")))
+        {
+            let is_any = self.is_keyword("any");
+            self.position += 2;
+            let path = self.parse_path()?;
+            self.expect_operator(",")?;
+            let expr = self.parse_or()?;
+            self.expect_operator(")")?;
+            return Ok(if is_any {
+                WhereExpr::Any(path, Box::new(expr))
+            } else {
+                WhereExpr::All(path, Box::new(expr))
+            });
+        }
+
+        let path = self.parse_path()?;
+        let op = match self.peek() {
+            Some(WhereToken::Operator(x)) => *x,
+            _ => return Ok(WhereExpr::Exists(path)),
+        };
+
+        let compare_op = match op {
+            "==" | "=" => WhereCompareOp::Eq,
+            "!=" => WhereCompareOp::Ne,
+            "<" => WhereCompareOp::Lt,
+            "<=" => WhereCompareOp::Le,
+            ">" => WhereCompareOp::Gt,
+            ">=" => WhereCompareOp::Ge,
+            "=~" | "!~" => {
+                self.position += 1;
+                let pattern = match self.next() {
+                    Some(WhereToken::String(x)) => x,
+                    _ => {
+                        return Err(format!(
+                            "expect a regex string after '{}' but got {}",
+                            op,
+                            self.describe_next()
+                        ));
+                    }
+                };
+                return match regex::Regex::new(&pattern) {
+                    Ok(r) => Ok(WhereExpr::Match(path, r, op == "!~")),
+                    Err(e) => Err(format!("invalid regex \"{}\", {}", pattern, e)),
+                };
+            }
+            _ => return Ok(WhereExpr::Exists(path)),
+        };
+        self.position += 1;
+
+        let literal = self.parse_literal()?;
+        Ok(WhereExpr::Compare(path, compare_op, literal))
+    }
+
+    fn parse_literal(&mut self) -> Result<WhereLiteral, String> {
+        match self.next() {
+            Some(WhereToken::Int(x)) => Ok(WhereLiteral::Int(x)),
+            Some(WhereToken::Float(x)) => Ok(WhereLiteral::Float(x)),
+            Some(WhereToken::String(x)) => Ok(WhereLiteral::String(x)),
+            Some(WhereToken::Ident(x)) => match x.as_str() {
+                "true" => Ok(WhereLiteral::Bool(true)),
+                "false" => Ok(WhereLiteral::Bool(false)),
+                _ => Ok(WhereLiteral::Ident(x)),
+            },
+            _ => {
+                self.position = self.position.saturating_sub(1);
+                Err(format!("expect a value but got {}", self.describe_next()))
+            }
+        }
+    }
+
+    fn parse_path(&mut self) -> Result<WherePath, String> {
+        let mut segments = Vec::new();
+        match self.next() {
+            Some(WhereToken::Ident(x)) => {
+                if x != "_" {
+                    segments.push(WherePathSegment::Field(x));
+                }
+            }
+            _ => {
+                self.position = self.position.saturating_sub(1);
+                return Err(format!(
+                    "expect a field path but got {}",
+                    self.describe_next()
+                ));
+            }
+        }
+
+        loop {
+            if self.is_operator(".") {
+                self.position += 1;
+                match self.next() {
+                    Some(WhereToken::Ident(x)) => segments.push(WherePathSegment::Field(x)),
+                    _ => {
+                        self.position = self.position.saturating_sub(1);
+                        return Err(format!(
+                            "expect a field name after '.' but got {}",
+                            self.describe_next()
+                        ));
+                    }
+                }
+            } else if self.is_operator("[") {
+                self.position += 1;
+                let segment = if self.is_operator("*") {
+                    self.position += 1;
+                    WherePathSegment::Wildcard
+                } else {
+                    WherePathSegment::Subscript(self.parse_literal()?)
+                };
+                self.expect_operator("]")?;
+                segments.push(segment);
+            } else {
+                break;
+            }
+        }
+
+        Ok(WherePath { segments })
+    }
+}
+
+fn literal_to_string(literal: &WhereLiteral) -> String {
+    match literal {
+        WhereLiteral::Bool(x) => x.to_string(),
+        WhereLiteral::Int(x) => x.to_string(),
+        WhereLiteral::Float(x) => x.to_string(),
+        WhereLiteral::String(x) => x.clone(),
+        WhereLiteral::Ident(x) => x.clone(),
+    }
+}
+
+impl WhereCompareOp {
+    fn test(&self, ordering: Ordering) -> bool {
+        match self {
+            WhereCompareOp::Eq => ordering == Ordering::Equal,
+            WhereCompareOp::Ne => ordering != Ordering::Equal,
+            WhereCompareOp::Lt => ordering == Ordering::Less,
+            WhereCompareOp::Le => ordering != Ordering::Greater,
+            WhereCompareOp::Gt => ordering == Ordering::Greater,
+            WhereCompareOp::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+fn compare_value(value: &ReflectValueRef, op: WhereCompareOp, literal: &WhereLiteral) -> bool {
+    let integer = match value {
+        ReflectValueRef::I32(x) => Some(*x as i128),
+        ReflectValueRef::I64(x) => Some(*x as i128),
+        ReflectValueRef::U32(x) => Some(*x as i128),
+        ReflectValueRef::U64(x) => Some(*x as i128),
+        ReflectValueRef::Enum(_, x) => Some(*x as i128),
+        _ => None,
+    };
+    let float = match value {
+        ReflectValueRef::F32(x) => Some(*x as f64),
+        ReflectValueRef::F64(x) => Some(*x),
+        _ => integer.map(|x| x as f64),
+    };
+
+    let ordering = match (value, literal) {
+        (
+            ReflectValueRef::Enum(enum_desc, x),
+            WhereLiteral::Ident(name) | WhereLiteral::String(name),
+        ) => enum_desc.value_by_name(name).map(|v| x.cmp(&v.value())),
+        (_, WhereLiteral::Int(l)) if integer.is_some() => integer.map(|x| x.cmp(l)),
+        (_, WhereLiteral::Int(l)) => float.and_then(|x| x.partial_cmp(&(*l as f64))),
+        (_, WhereLiteral::Float(l)) => float.and_then(|x| x.partial_cmp(l)),
+        (ReflectValueRef::Bool(x), WhereLiteral::Bool(l)) => Some(x.cmp(l)),
+        (ReflectValueRef::String(x), WhereLiteral::String(l)) => Some((*x).cmp(l.as_str())),
+        (ReflectValueRef::Bytes(x), WhereLiteral::String(l)) => Some((*x).cmp(l.as_bytes())),
+        _ => None,
+    };
+
+    match ordering {
+        Some(x) => op.test(x),
+        // Values can not be compared are always not equal
+        None => op == WhereCompareOp::Ne,
+    }
+}
+
+/// Visit all values the path points to, stop and return true when func returns true
+fn visit_path(
+    value: &ReflectValueRef,
+    segments: &[WherePathSegment],
+    func: &mut dyn FnMut(&ReflectValueRef) -> bool,
+) -> bool {
+    let (segment, rest) = match segments.split_first() {
+        Some(x) => x,
+        None => return func(value),
+    };
+
+    let message = match value {
+        ReflectValueRef::Message(m) => m.deref(),
+        _ => return false,
+    };
+
+    let field = match segment {
+        WherePathSegment::Field(name) => {
+            match utility::find_message_field(&message.descriptor_dyn(), name) {
+                Some(x) => x,
+                None => return false,
+            }
+        }
+        _ => return false,
+    };
+
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(v) => {
+            let v = match v.value() {
+                Some(x) => x,
+                None => field.get_singular_field_or_default(message),
+            };
+            visit_path(&v, rest, func)
+        }
+        ReflectFieldRef::Repeated(repeated) => match rest.split_first() {
+            Some((WherePathSegment::Subscript(index), rest)) => {
+                let index = match index {
+                    WhereLiteral::Int(x) if *x >= 0 => *x as usize,
+                    _ => return false,
+                };
+                if index >= repeated.len() {
+                    return false;
+                }
+                visit_path(&repeated.get(index), rest, func)
+            }
+            Some((WherePathSegment::Wildcard, rest)) => {
+                repeated.into_iter().any(|v| visit_path(&v, rest, func))
+            }
+            _ => repeated.into_iter().any(|v| visit_path(&v, rest, func)),
+        },
+        ReflectFieldRef::Map(map) => match rest.split_first() {
+            Some((WherePathSegment::Subscript(key), rest)) => {
+                let key = literal_to_string(key);
+                (&map).into_iter().any(|(k, v)| {
+                    utility::pb_value_to_string(&k) == key && visit_path(&v, rest, func)
+                })
+            }
+            Some((WherePathSegment::Wildcard, rest)) => {
+                (&map).into_iter().any(|(_, v)| visit_path(&v, rest, func))
+            }
+            _ => (&map).into_iter().any(|(_, v)| visit_path(&v, rest, func)),
+        },
+    }
+}
+
+fn validate_path(path: &WherePath, context: &WhereValueType) -> Result<WhereValueType, String> {
+    let mut current = context.clone();
+    let mut is_collection = false;
+    for segment in &path.segments {
+        match segment {
+            WherePathSegment::Field(name) => {
+                let message_desc = match &current {
+                    WhereValueType::Message(x) => x.clone(),
+                    WhereValueType::Scalar(_) => {
+                        return Err(format!("{} is not a field of message", name));
+                    }
+                };
+                let field = match utility::find_message_field(&message_desc, name) {
+                    Some(x) => x,
+                    None => {
+                        return Err(format!(
+                            "field {} not found in {}",
+                            name,
+                            message_desc.full_name()
+                        ));
+                    }
+                };
+
+                let (runtime_type, collection) = match field.runtime_field_type() {
+                    RuntimeFieldType::Singular(x) => (x, false),
+                    RuntimeFieldType::Repeated(x) => (x, true),
+                    RuntimeFieldType::Map(_, x) => (x, true),
+                };
+                current = match runtime_type {
+                    RuntimeType::Message(x) => WhereValueType::Message(x),
+                    x => WhereValueType::Scalar(x),
+                };
+                is_collection = collection;
+            }
+            WherePathSegment::Subscript(_) | WherePathSegment::Wildcard => {
+                if !is_collection {
+                    return Err("'[]' can only be used after repeated or map fields".to_string());
+                }
+                is_collection = false;
+            }
+        }
+    }
+
+    Ok(current)
+}
+
+fn validate_literal(value_type: &WhereValueType, literal: &WhereLiteral) -> Result<(), String> {
+    match (value_type, literal) {
+        (WhereValueType::Message(x), _) => Err(format!(
+            "message {} can not be compared with a value",
+            x.full_name()
+        )),
+        (
+            WhereValueType::Scalar(RuntimeType::Enum(enum_desc)),
+            WhereLiteral::Ident(name) | WhereLiteral::String(name),
+        ) => {
+            if enum_desc.value_by_name(name).is_none() {
+                Err(format!(
+                    "enum value {} not found in {}",
+                    name,
+                    enum_desc.full_name()
+                ))
+            } else {
+                Ok(())
+            }
+        }
+        (_, WhereLiteral::Ident(name)) => {
+            Err(format!("unknown value {}, use quotes for strings", name))
+        }
+        (
+            WhereValueType::Scalar(RuntimeType::String | RuntimeType::VecU8),
+            WhereLiteral::Int(_) | WhereLiteral::Float(_) | WhereLiteral::Bool(_),
+        ) => Err(format!(
+            "string can not be compared with {}",
+            literal_to_string(literal)
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl WhereExpr {
+    fn evaluate(&self, value: &ReflectValueRef) -> bool {
+        match self {
+            WhereExpr::Or(l, r) => l.evaluate(value) || r.evaluate(value),
+            WhereExpr::And(l, r) => l.evaluate(value) && r.evaluate(value),
+            WhereExpr::Not(x) => !x.evaluate(value),
+            WhereExpr::Compare(path, op, literal) => visit_path(value, &path.segments, &mut |v| {
+                compare_value(v, *op, literal)
+            }),
+            WhereExpr::Match(path, rule, negative) => visit_path(value, &path.segments, &mut |v| {
+                rule.is_match(&utility::pb_value_to_string(v)) != *negative
+            }),
+            WhereExpr::Exists(path) => visit_path(value, &path.segments, &mut |v| {
+                !utility::is_pb_value_empty(v)
+            }),
+            WhereExpr::Any(path, expr) => {
+                visit_path(value, &path.segments, &mut |v| expr.evaluate(v))
+            }
+            WhereExpr::All(path, expr) => {
+                !visit_path(value, &path.segments, &mut |v| !expr.evaluate(v))
+            }
+        }
+    }
+
+    fn validate(&self, context: &WhereValueType) -> Result<(), String> {
+        match self {
+            WhereExpr::Or(l, r) | WhereExpr::And(l, r) => {
+                l.validate(context)?;
+                r.validate(context)
+            }
+            WhereExpr::Not(x) => x.validate(context),
+            WhereExpr::Compare(path, _, literal) => {
+                validate_literal(&validate_path(path, context)?, literal)
+            }
+            WhereExpr::Match(path, _, _) | WhereExpr::Exists(path) => {
+                validate_path(path, context).map(|_| ())
+            }
+            WhereExpr::Any(path, expr) | WhereExpr::All(path, expr) => {
+                expr.validate(&validate_path(path, context)?)
+            }
+        }
+    }
+}
+
+/// Row filter built from `--where` expressions, such as `Id == 10001 && Level > 5`
+pub struct RowFilter {
+    expr: WhereExpr,
+}
+
+impl RowFilter {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = WhereParser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let expr = parser.parse_or()?;
+        if parser.peek().is_some() {
+            return Err(format!("unexpected {}", parser.describe_next()));
+        }
+
+        Ok(RowFilter { expr })
+    }
+
+    /// Build filter from all expressions, they will be joined by `&&`
+    pub fn build(expressions: &[String]) -> Result<Option<Self>, String> {
+        let mut ret: Option<WhereExpr> = None;
+        for expression in expressions {
+            if expression.trim().is_empty() {
+                continue;
+            }
+
+            let expr = match Self::parse(expression) {
+                Ok(x) => x.expr,
+                Err(e) => return Err(format!("invalid expression \"{}\", {}", expression, e)),
+            };
+            ret = Some(match ret {
+                Some(x) => WhereExpr::And(Box::new(x), Box::new(expr)),
+                None => expr,
+            });
+        }
+
+        Ok(ret.map(|expr| RowFilter { expr }))
+    }
+
+    /// Check all field paths, enum names and value types for the message type
+    pub fn validate(&self, message_desc: &MessageDescriptor) -> Result<(), String> {
+        self.expr
+            .validate(&WhereValueType::Message(message_desc.clone()))
+    }
+
+    pub fn matches(&self, message: &dyn MessageDyn) -> bool {
+        self.expr
+            .evaluate(&ReflectValueRef::Message(MessageRef::new(message)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::descriptor::FileDescriptorProto;
+    use protobuf::reflect::FileDescriptor;
+
+    const TEST_PROTO: &str = r#"
+        name: "row_filter_test.proto"
+        package: "demo"
+        syntax: "proto3"
+        enum_type {
+            name: "Kind"
+            value { name: "KIND_NONE" number: 0 }
+            value { name: "KIND_A" number: 1 }
+            value { name: "KIND_B" number: 2 }
+        }
+        message_type {
+            name: "Reward"
+            field { name: "item_id" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+            field { name: "count" number: 2 label: LABEL_OPTIONAL type: TYPE_INT32 }
+        }
+        message_type {
+            name: "Item"
+            field { name: "id" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+            field { name: "name" number: 2 label: LABEL_OPTIONAL type: TYPE_STRING }
+            field { name: "kind" number: 3 label: LABEL_OPTIONAL type: TYPE_ENUM type_name: ".demo.Kind" }
+            field { name: "rewards" number: 4 label: LABEL_REPEATED type: TYPE_MESSAGE type_name: ".demo.Reward" }
+            field { name: "attrs" number: 5 label: LABEL_REPEATED type: TYPE_MESSAGE type_name: ".demo.Item.AttrsEntry" }
+            field { name: "names" number: 6 label: LABEL_REPEATED type: TYPE_MESSAGE type_name: ".demo.Item.NamesEntry" }
+            nested_type {
+                name: "AttrsEntry"
+                field { name: "key" number: 1 label: LABEL_OPTIONAL type: TYPE_STRING }
+                field { name: "value" number: 2 label: LABEL_OPTIONAL type: TYPE_INT32 }
+                options { map_entry: true }
+            }
+            nested_type {
+                name: "NamesEntry"
+                field { name: "key" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+                field { name: "value" number: 2 label: LABEL_OPTIONAL type: TYPE_STRING }
+                options { map_entry: true }
+            }
+        }
+    "#;
+
+    const TEST_ITEM: &str = r#"
+        id: 10
+        name: "sword"
+        kind: KIND_A
+        rewards { item_id: 1 count: 2 }
+        rewards { item_id: 2 count: 8 }
+        attrs { key: "hp" value: 100 }
+        attrs { key: "mp" value: 5 }
+        names { key: 2 value: "two" }
+        names { key: 10 value: "ten" }
+    "#;
+
+    fn item_descriptor() -> MessageDescriptor {
+        let proto: FileDescriptorProto = protobuf::text_format::parse_from_str(TEST_PROTO).unwrap();
+        FileDescriptor::new_dynamic(proto, &[])
+            .unwrap()
+            .message_by_package_relative_name("Item")
+            .unwrap()
+    }
+
+    fn build_filter(expression: &str) -> Result<RowFilter, String> {
+        let filter = RowFilter::parse(expression)?;
+        filter.validate(&item_descriptor())?;
+        Ok(filter)
+    }
+
+    fn check(expression: &str, item: &str) -> bool {
+        let mut message = item_descriptor().new_instance();
+        protobuf::text_format::merge_from_str(&mut *message, item).unwrap();
+        build_filter(expression).unwrap().matches(&*message)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(check("id == 10 || id == 1 && name == \"axe\"", TEST_ITEM));
+        assert!(!check(
+            "(id == 10 || id == 1) && name == \"axe\"",
+            TEST_ITEM
+        ));
+        assert!(check("id == 1 and name == \"axe\" or id == 10", TEST_ITEM));
+    }
+
+    #[test]
+    fn not_applies_to_the_next_expression() {
+        assert!(!check("!id == 10", TEST_ITEM));
+        assert!(check("!id == 10 || name == \"sword\"", TEST_ITEM));
+        assert!(!check("!(id == 10 || name == \"axe\")", TEST_ITEM));
+        assert!(check("not not id == 10", TEST_ITEM));
+        assert!(check("!!name", TEST_ITEM));
+        assert!(check("!name", "id: 10"));
+    }
+
+    #[test]
+    fn build_joins_expressions_with_and() {
+        let filter = RowFilter::build(&["id == 10".to_string(), "  ".to_string()]).unwrap();
+        assert!(filter.is_some());
+        let filter =
+            RowFilter::build(&["id == 10".to_string(), "name == \"axe\"".to_string()]).unwrap();
+        let mut message = item_descriptor().new_instance();
+        protobuf::text_format::merge_from_str(&mut *message, TEST_ITEM).unwrap();
+        assert!(!filter.unwrap().matches(&*message));
+        assert!(RowFilter::build(&[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn any_and_all() {
+        assert!(check("any(rewards, count > 5)", TEST_ITEM));
+        assert!(!check("any(rewards, count > 10)", TEST_ITEM));
+        assert!(check("all(rewards, count > 1)", TEST_ITEM));
+        assert!(!check("all(rewards, count > 5)", TEST_ITEM));
+        assert!(check("all(attrs, _ >= 5)", TEST_ITEM));
+        assert!(check("any(rewards, item_id == 2 && count == 8)", TEST_ITEM));
+        assert!(!check(
+            "any(rewards, item_id == 1 && count == 8)",
+            TEST_ITEM
+        ));
+    }
+
+    #[test]
+    fn any_and_all_of_empty_list() {
+        assert!(!check("any(rewards, count > 5)", "id: 10"));
+        // all() of an empty list is vacuously true
+        assert!(check("all(rewards, count > 5)", "id: 10"));
+        assert!(check("all(attrs, _ > 1000)", "id: 10"));
+    }
+
+    #[test]
+    fn wildcard_and_subscripts() {
+        assert!(check("rewards[*].count == 8", TEST_ITEM));
+        assert!(!check("rewards[*].count == 3", TEST_ITEM));
+        assert!(check("rewards.count == 8", TEST_ITEM));
+        assert!(check("rewards[0].count == 2", TEST_ITEM));
+        assert!(!check("rewards[0].count == 8", TEST_ITEM));
+        assert!(!check("rewards[2].count >= 0", TEST_ITEM));
+        assert!(!check("rewards[-1].count >= 0", TEST_ITEM));
+        assert!(check("attrs[hp] == 100", TEST_ITEM));
+        assert!(check("attrs[\"mp\"] < 10", TEST_ITEM));
+        assert!(!check("attrs[sp] >= 0", TEST_ITEM));
+        assert!(check("attrs[*] == 5", TEST_ITEM));
+        assert!(check("names[10] == \"ten\"", TEST_ITEM));
+        assert!(!check("names[1] == \"ten\"", TEST_ITEM));
+    }
+
+    #[test]
+    fn enum_names_and_quoted_names() {
+        assert!(check("kind == KIND_A", TEST_ITEM));
+        assert!(check("kind == \"KIND_A\"", TEST_ITEM));
+        assert!(check("kind == 1", TEST_ITEM));
+        assert!(check("kind != KIND_B", TEST_ITEM));
+        assert!(check("kind < KIND_B", TEST_ITEM));
+        assert!(check("kind == KIND_NONE", "id: 10"));
+
+        assert!(build_filter("kind == KIND_C").is_err());
+        assert!(build_filter("kind == \"KIND_C\"").is_err());
+        // Bare identifiers are only enum names, strings must be quoted
+        assert!(build_filter("name == sword").is_err());
+        assert!(check("name == \"sword\"", TEST_ITEM));
+    }
+
+    #[test]
+    fn regex_match() {
+        assert!(check("name =~ \"^sw\"", TEST_ITEM));
+        assert!(!check("name =~ \"^ax\"", TEST_ITEM));
+        assert!(!check("name !~ \"^sw\"", TEST_ITEM));
+        assert!(check("name !~ \"^ax\"", TEST_ITEM));
+        assert!(check("kind =~ \"_A$\"", TEST_ITEM));
+        assert!(check("any(rewards, item_id =~ \"^2$\")", TEST_ITEM));
+        assert!(RowFilter::parse("name =~ sword").is_err());
+        assert!(RowFilter::parse("name =~ \"(\"").is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        for expression in [
+            "id ==",
+            "(id == 10",
+            "id == 10)",
+            "id == 10 name",
+            "name == \"sword",
+            "id # 10",
+            "rewards[0",
+            "rewards.",
+            "any(rewards count > 1)",
+            "&& id == 10",
+        ] {
+            assert!(
+                RowFilter::parse(expression).is_err(),
+                "{} should not be parsed",
+                expression
+            );
+        }
+
+        let err = RowFilter::build(&["id ==".to_string()]).err().unwrap();
+        assert!(err.contains("invalid expression \"id ==\""), "{}", err);
+    }
+
+    #[test]
+    fn validate_errors() {
+        assert!(build_filter("level > 1").is_err());
+        assert!(build_filter("id[0] == 1").is_err());
+        assert!(build_filter("rewards == 1").is_err());
+        assert!(build_filter("name > 1").is_err());
+        assert!(build_filter("any(rewards, level > 1)").is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+
+use base64::Engine;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{
+    FieldDescriptor, MessageDescriptor, ReflectFieldRef, ReflectValueBox, ReflectValueRef,
+    RuntimeFieldType, RuntimeType,
+};
+
+use super::row_projection::{ProjectionCursor, RowProjection};
+use super::utility;
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlattenMapKey {
+    Int(i128),
+    String(String),
+}
+
+/// Part of column key, columns are sorted by field order in descriptor, then indexes of repeated fields or keys of map fields
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlattenPathPart {
+    Field(usize),
+    Index(usize),
+    Key(FlattenMapKey),
+}
+
+pub type FlattenColumnKey = Vec<FlattenPathPart>;
+
+pub struct FlattenColumn {
+    /// Column name, such as `Cost.Type`, `Rewards[0].Id` and `Attrs[hp]`
+    pub path: String,
+    /// Fields from the row message to the column, one for each `FlattenPathPart::Field` in column key
+    pub fields: Vec<FieldDescriptor>,
+}
+
+/// Rows with nested messages flattened into columns, repeated fields are expanded into indexed columns up to the max length seen
+#[derive(Default)]
+pub struct FlattenTable {
+    columns: BTreeMap<FlattenColumnKey, FlattenColumn>,
+    rows: Vec<HashMap<FlattenColumnKey, ReflectValueBox>>,
+}
+
+struct FlattenContext<'a> {
+    key: FlattenColumnKey,
+    path: String,
+    fields: Vec<FieldDescriptor>,
+    message_stack: Vec<String>,
+    row: &'a mut HashMap<FlattenColumnKey, ReflectValueBox>,
+}
+
+/// Key to sort entries of map fields, integer keys are ordered by value
+pub fn map_key_to_flatten_key(key: &ReflectValueRef) -> FlattenMapKey {
+    match key {
+        ReflectValueRef::I32(x) => FlattenMapKey::Int(*x as i128),
+        ReflectValueRef::I64(x) => FlattenMapKey::Int(*x as i128),
+        ReflectValueRef::U32(x) => FlattenMapKey::Int(*x as i128),
+        ReflectValueRef::U64(x) => FlattenMapKey::Int(*x as i128),
+        _ => FlattenMapKey::String(utility::pb_value_to_string(key)),
+    }
+}
+
+/// Render value of a cell, enums by name and bytes as base64
+pub fn flatten_value_to_string(value: &ReflectValueBox) -> String {
+    match value {
+        ReflectValueBox::Bytes(x) => base64::engine::general_purpose::STANDARD.encode(x),
+        _ => utility::pb_value_to_string(&value.as_value_ref()),
+    }
+}
+
+fn has_presence(field: &FieldDescriptor) -> bool {
+    field.containing_oneof_including_synthetic().is_some()
+}
+
+impl FlattenTable {
+    pub fn new() -> Self {
+        FlattenTable::default()
+    }
+
+    /// Add a row, only fields selected by projection are added if it's set
+    pub fn add_row(&mut self, message: &dyn MessageDyn, projection: Option<&RowProjection>) {
+        let mut row = HashMap::new();
+        let mut context = FlattenContext {
+            key: Vec::new(),
+            path: String::new(),
+            fields: Vec::new(),
+            message_stack: vec![message.descriptor_dyn().full_name().to_string()],
+            row: &mut row,
+        };
+        let cursor = projection.map(|x| x.cursor());
+        self.flatten_message(message, &mut context, cursor.as_ref());
+        self.rows.push(row);
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = (&FlattenColumnKey, &FlattenColumn)> {
+        self.columns.iter()
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn get(&self, row: usize, key: &FlattenColumnKey) -> Option<&ReflectValueBox> {
+        self.rows.get(row).and_then(|x| x.get(key))
+    }
+
+    fn add_column(&mut self, context: &FlattenContext) {
+        if self.columns.contains_key(&context.key) {
+            return;
+        }
+
+        self.columns.insert(
+            context.key.clone(),
+            FlattenColumn {
+                path: context.path.clone(),
+                fields: context.fields.clone(),
+            },
+        );
+    }
+
+    fn add_value(&mut self, context: &mut FlattenContext, value: ReflectValueBox) {
+        self.add_column(context);
+        context.row.insert(context.key.clone(), value);
+    }
+
+    fn flatten_message(
+        &mut self,
+        message: &dyn MessageDyn,
+        context: &mut FlattenContext,
+        cursor: Option<&ProjectionCursor>,
+    ) {
+        let message_desc = message.descriptor_dyn();
+        for (field_index, field) in message_desc.fields().enumerate() {
+            let field_cursor = match cursor {
+                Some(x) => match x.field(&message_desc, &field) {
+                    Some(y) => Some(y),
+                    None => continue,
+                },
+                None => None,
+            };
+            let old_path_len = context.path.len();
+            if !context.path.is_empty() {
+                context.path.push('.');
+            }
+            context.path.push_str(field.name());
+            context.key.push(FlattenPathPart::Field(field_index));
+            context.fields.push(field.clone());
+
+            match field.get_reflect(message) {
+                ReflectFieldRef::Optional(v) => match v.value() {
+                    Some(ReflectValueRef::Message(m)) => {
+                        self.flatten_nested_message(m.deref(), context, field_cursor.as_ref());
+                    }
+                    Some(x) => self.add_value(context, x.to_box()),
+                    None => match field.runtime_field_type() {
+                        RuntimeFieldType::Singular(RuntimeType::Message(sub_message_desc)) => {
+                            self.flatten_empty_message(
+                                &sub_message_desc,
+                                context,
+                                field_cursor.as_ref(),
+                            );
+                        }
+                        _ => {
+                            if has_presence(&field) {
+                                self.add_column(context);
+                            } else {
+                                self.add_value(
+                                    context,
+                                    field.get_singular_field_or_default(message).to_box(),
+                                );
+                            }
+                        }
+                    },
+                },
+                ReflectFieldRef::Repeated(v) => {
+                    for (index, x) in v.into_iter().enumerate() {
+                        let element_cursor = match &field_cursor {
+                            Some(c) => match c.element(&index.to_string()) {
+                                Some(y) => Some(y),
+                                None => continue,
+                            },
+                            None => None,
+                        };
+                        let path_len = context.path.len();
+                        context.path.push_str(&format!("[{}]", index));
+                        context.key.push(FlattenPathPart::Index(index));
+                        self.flatten_element(&x, context, element_cursor.as_ref());
+                        context.key.pop();
+                        context.path.truncate(path_len);
+                    }
+                }
+                ReflectFieldRef::Map(v) => {
+                    for (k, x) in &v {
+                        let element_cursor = match &field_cursor {
+                            Some(c) => match c.element(&utility::pb_value_to_string(&k)) {
+                                Some(y) => Some(y),
+                                None => continue,
+                            },
+                            None => None,
+                        };
+                        let path_len = context.path.len();
+                        context
+                            .path
+                            .push_str(&format!("[{}]", utility::pb_value_to_string(&k)));
+                        context
+                            .key
+                            .push(FlattenPathPart::Key(map_key_to_flatten_key(&k)));
+                        self.flatten_element(&x, context, element_cursor.as_ref());
+                        context.key.pop();
+                        context.path.truncate(path_len);
+                    }
+                }
+            }
+
+            context.fields.pop();
+            context.key.pop();
+            context.path.truncate(old_path_len);
+        }
+    }
+
+    fn flatten_element(
+        &mut self,
+        value: &ReflectValueRef,
+        context: &mut FlattenContext,
+        cursor: Option<&ProjectionCursor>,
+    ) {
+        match value {
+            ReflectValueRef::Message(m) => self.flatten_nested_message(m.deref(), context, cursor),
+            _ => self.add_value(context, value.to_box()),
+        }
+    }
+
+    fn flatten_nested_message(
+        &mut self,
+        message: &dyn MessageDyn,
+        context: &mut FlattenContext,
+        cursor: Option<&ProjectionCursor>,
+    ) {
+        context
+            .message_stack
+            .push(message.descriptor_dyn().full_name().to_string());
+        self.flatten_message(message, context, cursor);
+        context.message_stack.pop();
+    }
+
+    /// Add columns of unset message fields, so all rows have the same columns
+    fn flatten_empty_message(
+        &mut self,
+        message_desc: &MessageDescriptor,
+        context: &mut FlattenContext,
+        cursor: Option<&ProjectionCursor>,
+    ) {
+        // Stop at recursive messages
+        if context
+            .message_stack
+            .iter()
+            .any(|x| x == message_desc.full_name())
+        {
+            return;
+        }
+
+        context
+            .message_stack
+            .push(message_desc.full_name().to_string());
+        for (field_index, field) in message_desc.fields().enumerate() {
+            let field_cursor = match cursor {
+                Some(x) => match x.field(message_desc, &field) {
+                    Some(y) => Some(y),
+                    None => continue,
+                },
+                None => None,
+            };
+            if let RuntimeFieldType::Singular(runtime_type) = field.runtime_field_type() {
+                let old_path_len = context.path.len();
+                context.path.push('.');
+                context.path.push_str(field.name());
+                context.key.push(FlattenPathPart::Field(field_index));
+                context.fields.push(field.clone());
+
+                match runtime_type {
+                    RuntimeType::Message(sub_message_desc) => {
+                        self.flatten_empty_message(
+                            &sub_message_desc,
+                            context,
+                            field_cursor.as_ref(),
+                        );
+                    }
+                    _ => self.add_column(context),
+                }
+
+                context.fields.pop();
+                context.key.pop();
+                context.path.truncate(old_path_len);
+            }
+        }
+        context.message_stack.pop();
+    }
+}
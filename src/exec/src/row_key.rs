@@ -0,0 +1,129 @@
+use protobuf::reflect::{FieldDescriptor, MessageDescriptor};
+use protobuf::{Message, MessageDyn};
+use xresloader_protocol::proto::xresloader_ue;
+
+use super::data_check_unique_tag::FIELD_UNIQUE_TAG_NUMBER;
+use super::utility;
+
+/// Fields used to identify a row of a table.
+/// By default, fields with org.xresloader.ue.key_tag are used first, then the first group of org.xresloader.field_unique_tag,
+/// and the first field at last.
+pub struct RowKey {
+    pub fields: Vec<FieldDescriptor>,
+}
+
+impl RowKey {
+    pub fn from_field_names(
+        message_desc: &MessageDescriptor,
+        field_names: &[String],
+    ) -> Result<Self, String> {
+        let mut fields = Vec::with_capacity(field_names.len());
+        for field_name in field_names {
+            match message_desc.field_by_name(field_name.trim()) {
+                Some(x) => fields.push(x),
+                None => {
+                    return Err(format!(
+                        "field {} not found in {}",
+                        field_name,
+                        message_desc.full_name()
+                    ));
+                }
+            }
+        }
+
+        Ok(RowKey { fields })
+    }
+
+    pub fn from_unique_tag(message_desc: &MessageDescriptor, tag: &str) -> Result<Self, String> {
+        let fields: Vec<FieldDescriptor> = message_desc
+            .fields()
+            .filter(|field| {
+                field.proto().options.as_ref().is_some_and(|options| {
+                    utility::get_repeated_string_option(
+                        options.unknown_fields(),
+                        FIELD_UNIQUE_TAG_NUMBER,
+                    )
+                    .iter()
+                    .any(|x| x == tag)
+                })
+            })
+            .collect();
+
+        if fields.is_empty() {
+            return Err(format!(
+                "there is no field with org.xresloader.field_unique_tag \"{}\" in {}",
+                tag,
+                message_desc.full_name()
+            ));
+        }
+
+        Ok(RowKey { fields })
+    }
+
+    /// Fields with org.xresloader.ue.key_tag, the field with larger key_tag goes first
+    pub fn from_key_tag(message_desc: &MessageDescriptor) -> Option<Self> {
+        let mut fields: Vec<(i64, FieldDescriptor)> = message_desc
+            .fields()
+            .filter_map(|field| {
+                let key_tag = field
+                    .proto()
+                    .options
+                    .as_ref()
+                    .and_then(|options| xresloader_ue::exts::key_tag.get(options))
+                    .unwrap_or(0);
+                if key_tag > 0 {
+                    Some((key_tag, field))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if fields.is_empty() {
+            return None;
+        }
+
+        fields.sort_by_key(|x| std::cmp::Reverse(x.0));
+        Some(RowKey {
+            fields: fields.into_iter().map(|x| x.1).collect(),
+        })
+    }
+
+    pub fn from_descriptor(message_desc: &MessageDescriptor) -> Self {
+        if let Some(x) = Self::from_key_tag(message_desc) {
+            return x;
+        }
+
+        for field in message_desc.fields() {
+            if let Some(tag) = field.proto().options.as_ref().and_then(|options| {
+                utility::get_repeated_string_option(
+                    options.unknown_fields(),
+                    FIELD_UNIQUE_TAG_NUMBER,
+                )
+                .into_iter()
+                .find(|x| !x.is_empty())
+            }) && let Ok(x) = Self::from_unique_tag(message_desc, &tag)
+            {
+                return x;
+            }
+        }
+
+        RowKey {
+            fields: message_desc.fields().take(1).collect(),
+        }
+    }
+
+    pub fn field_names(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect()
+    }
+
+    pub fn values(&self, message: &dyn MessageDyn) -> Vec<String> {
+        self.fields
+            .iter()
+            .map(|field| utility::pb_field_value_to_string(field, message))
+            .collect()
+    }
+}
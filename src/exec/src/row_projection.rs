@@ -0,0 +1,412 @@
+use std::ops::Deref;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{
+    FieldDescriptor, MessageDescriptor, ReflectFieldRef, ReflectValueBox, ReflectValueRef,
+    RuntimeFieldType, RuntimeType,
+};
+
+use super::utility;
+
+#[derive(Clone, PartialEq)]
+enum ProjectionSegment {
+    Field(String),
+    /// `*`, all fields of message
+    AnyField,
+    /// `[0]`, `["key"]` or `[*]`, elements of repeated or map fields, `None` means all elements
+    Element(Option<String>),
+}
+
+/// Selected paths are merged into a tree, a node is selected as a whole if any path ends at it
+#[derive(Default)]
+struct ProjectionNode {
+    whole: bool,
+    children: Vec<(ProjectionSegment, ProjectionNode)>,
+}
+
+impl ProjectionNode {
+    fn insert(&mut self, segments: &[ProjectionSegment]) {
+        let (segment, rest) = match segments.split_first() {
+            Some(x) => x,
+            None => {
+                self.whole = true;
+                return;
+            }
+        };
+
+        let child = match self.children.iter().position(|(x, _)| x == segment) {
+            Some(index) => &mut self.children[index].1,
+            None => {
+                self.children
+                    .push((segment.clone(), ProjectionNode::default()));
+                &mut self.children.last_mut().unwrap().1
+            }
+        };
+        child.insert(rest);
+    }
+}
+
+fn parse_path(input: &str) -> Result<Vec<ProjectionSegment>, String> {
+    let chars: Vec<char> = input.trim().chars().collect();
+    let mut ret = Vec::new();
+    let mut i = 0;
+    let mut expect_field = true;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                if expect_field {
+                    return Err(format!("unexpected '.' at {}", i));
+                }
+                expect_field = true;
+                i += 1;
+            }
+            '[' => {
+                if expect_field {
+                    return Err(format!("unexpected '[' at {}", i));
+                }
+                let end = match chars[i..].iter().position(|x| *x == ']') {
+                    Some(x) => i + x,
+                    None => return Err("missing ']'".to_string()),
+                };
+                let key: String = chars[i + 1..end].iter().collect();
+                let key = key.trim();
+                if key.is_empty() {
+                    return Err("empty key in '[]'".to_string());
+                }
+                ret.push(ProjectionSegment::Element(if key == "*" {
+                    None
+                } else {
+                    Some(key.trim_matches(|c| c == '"' || c == '\'').to_string())
+                }));
+                i = end + 1;
+            }
+            '*' => {
+                if !expect_field {
+                    return Err(format!("unexpected '*' at {}", i));
+                }
+                ret.push(ProjectionSegment::AnyField);
+                expect_field = false;
+                i += 1;
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                if !expect_field {
+                    return Err(format!("unexpected '{}' at {}", c, i));
+                }
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                ret.push(ProjectionSegment::Field(chars[start..i].iter().collect()));
+                expect_field = false;
+            }
+            c => {
+                return Err(format!("unexpected '{}' at {}", c, i));
+            }
+        }
+    }
+
+    if expect_field {
+        return Err("expect a field name".to_string());
+    }
+
+    Ok(ret)
+}
+
+fn copy_field(source: &dyn MessageDyn, target: &mut dyn MessageDyn, field: &FieldDescriptor) {
+    match field.get_reflect(source) {
+        ReflectFieldRef::Optional(v) => {
+            if let Some(x) = v.value() {
+                field.set_singular_field(target, x.to_box());
+            }
+        }
+        ReflectFieldRef::Repeated(v) => {
+            let mut target_repeated = field.mut_repeated(target);
+            for x in v {
+                target_repeated.push(x.to_box());
+            }
+        }
+        ReflectFieldRef::Map(v) => {
+            let mut target_map = field.mut_map(target);
+            for (k, x) in &v {
+                target_map.insert(k.to_box(), x.to_box());
+            }
+        }
+    }
+}
+
+/// Selected nodes at a position of message, used to walk messages with the selection
+#[derive(Clone)]
+pub struct ProjectionCursor<'a> {
+    nodes: Vec<&'a ProjectionNode>,
+}
+
+impl<'a> ProjectionCursor<'a> {
+    /// The whole value at this position is selected
+    pub fn is_whole(&self) -> bool {
+        self.nodes.iter().any(|x| x.whole)
+    }
+
+    /// Cursor of a field of message, `None` if the field is not selected
+    pub fn field(&self, message_desc: &MessageDescriptor, field: &FieldDescriptor) -> Option<Self> {
+        if self.is_whole() {
+            return Some(self.clone());
+        }
+
+        let nodes: Vec<&'a ProjectionNode> = self
+            .nodes
+            .iter()
+            .flat_map(|node| node.children.iter())
+            .filter(|(segment, _)| match segment {
+                ProjectionSegment::AnyField => true,
+                ProjectionSegment::Field(name) => utility::find_message_field(message_desc, name)
+                    .is_some_and(|x| x.number() == field.number()),
+                ProjectionSegment::Element(_) => false,
+            })
+            .map(|(_, child)| child)
+            .collect();
+
+        if nodes.is_empty() {
+            None
+        } else {
+            Some(ProjectionCursor { nodes })
+        }
+    }
+
+    /// Cursor of an element of repeated field(by index) or map field(by key), `None` if the element is not selected.
+    /// Field selections directly under the repeated or map field apply to all elements.
+    pub fn element(&self, key: &str) -> Option<Self> {
+        if self.is_whole() {
+            return Some(self.clone());
+        }
+
+        let mut nodes: Vec<&'a ProjectionNode> = Vec::new();
+        for node in &self.nodes {
+            for (segment, child) in &node.children {
+                let selected = match segment {
+                    ProjectionSegment::Element(None) => child,
+                    ProjectionSegment::Element(Some(x)) if x == key => child,
+                    ProjectionSegment::Element(_) => continue,
+                    _ => *node,
+                };
+                if !nodes.iter().any(|x| std::ptr::eq(*x, selected)) {
+                    nodes.push(selected);
+                }
+            }
+        }
+
+        if nodes.is_empty() {
+            None
+        } else {
+            Some(ProjectionCursor { nodes })
+        }
+    }
+}
+
+fn project_value(value: &ReflectValueRef, cursor: &ProjectionCursor) -> ReflectValueBox {
+    if cursor.is_whole() {
+        return value.to_box();
+    }
+
+    match value {
+        ReflectValueRef::Message(m) => {
+            let mut target = m.descriptor_dyn().new_instance();
+            project_message(m.deref(), target.as_mut(), cursor);
+            ReflectValueBox::Message(target)
+        }
+        _ => value.to_box(),
+    }
+}
+
+fn project_message(
+    source: &dyn MessageDyn,
+    target: &mut dyn MessageDyn,
+    cursor: &ProjectionCursor,
+) {
+    if cursor.is_whole() {
+        target
+            .merge_from_bytes_dyn(&source.write_to_bytes_dyn().unwrap_or_default())
+            .unwrap_or_default();
+        return;
+    }
+
+    let message_desc = source.descriptor_dyn();
+    for field in message_desc.fields() {
+        let field_cursor = match cursor.field(&message_desc, &field) {
+            Some(x) => x,
+            None => continue,
+        };
+
+        if field_cursor.is_whole() {
+            copy_field(source, target, &field);
+            continue;
+        }
+
+        match field.get_reflect(source) {
+            ReflectFieldRef::Optional(v) => {
+                if let Some(x) = v.value() {
+                    field.set_singular_field(target, project_value(&x, &field_cursor));
+                }
+            }
+            ReflectFieldRef::Repeated(v) => {
+                let mut target_repeated = field.mut_repeated(target);
+                for (index, x) in v.into_iter().enumerate() {
+                    if let Some(element_cursor) = field_cursor.element(&index.to_string()) {
+                        target_repeated.push(project_value(&x, &element_cursor));
+                    }
+                }
+            }
+            ReflectFieldRef::Map(v) => {
+                let mut target_map = field.mut_map(target);
+                for (k, x) in &v {
+                    if let Some(element_cursor) =
+                        field_cursor.element(&utility::pb_value_to_string(&k))
+                    {
+                        target_map.insert(k.to_box(), project_value(&x, &element_cursor));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn validate_message(
+    node: &ProjectionNode,
+    message_desc: &MessageDescriptor,
+    path: &str,
+    skip_elements: bool,
+) -> Result<(), String> {
+    for (segment, child) in &node.children {
+        match segment {
+            ProjectionSegment::Field(name) => {
+                match utility::find_message_field(message_desc, name) {
+                    Some(field) => validate_field(&field, child, path)?,
+                    None => {
+                        return Err(format!(
+                            "field {} not found in {}",
+                            name,
+                            message_desc.full_name()
+                        ));
+                    }
+                }
+            }
+            ProjectionSegment::AnyField => {
+                for field in message_desc.fields() {
+                    validate_field(&field, child, path)?;
+                }
+            }
+            ProjectionSegment::Element(_) => {
+                if !skip_elements {
+                    return Err(format!(
+                        "{} is not a repeated or map field, '[]' can not be used",
+                        path
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_value(
+    value_type: &RuntimeType,
+    node: &ProjectionNode,
+    path: &str,
+    skip_elements: bool,
+) -> Result<(), String> {
+    match value_type {
+        RuntimeType::Message(message_desc) => {
+            validate_message(node, message_desc, path, skip_elements)
+        }
+        _ => {
+            for (segment, _) in &node.children {
+                match segment {
+                    ProjectionSegment::Element(_) => {
+                        if !skip_elements {
+                            return Err(format!(
+                                "{} is not a repeated or map field, '[]' can not be used",
+                                path
+                            ));
+                        }
+                    }
+                    _ => return Err(format!("{} is not a message", path)),
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn validate_field(
+    field: &FieldDescriptor,
+    node: &ProjectionNode,
+    path: &str,
+) -> Result<(), String> {
+    let field_path = if path.is_empty() {
+        field.name().to_string()
+    } else {
+        format!("{}.{}", path, field.name())
+    };
+
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(x) => validate_value(&x, node, &field_path, false),
+        RuntimeFieldType::Repeated(x) | RuntimeFieldType::Map(_, x) => {
+            for (segment, child) in &node.children {
+                if let ProjectionSegment::Element(_) = segment {
+                    validate_value(&x, child, &format!("{}[]", field_path), false)?;
+                }
+            }
+            // Field selections directly under repeated or map field apply to all elements
+            validate_value(&x, node, &field_path, true)
+        }
+    }
+}
+
+/// Projection of rows built from `--select` paths, such as `Cost.*` and `Rewards[0].Id`
+pub struct RowProjection {
+    root: ProjectionNode,
+}
+
+impl RowProjection {
+    pub fn build(paths: &[String]) -> Result<Option<Self>, String> {
+        let mut root = ProjectionNode::default();
+        let mut has_path = false;
+        for input in paths {
+            for path in input.split(',') {
+                if path.trim().is_empty() {
+                    continue;
+                }
+
+                match parse_path(path) {
+                    Ok(x) => root.insert(&x),
+                    Err(e) => return Err(format!("invalid field path \"{}\", {}", path, e)),
+                }
+                has_path = true;
+            }
+        }
+
+        if has_path {
+            Ok(Some(RowProjection { root }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check all field names in paths for the message type
+    pub fn validate(&self, message_desc: &MessageDescriptor) -> Result<(), String> {
+        validate_message(&self.root, message_desc, "", false)
+    }
+
+    /// Create a new message with only selected fields
+    pub fn project(&self, message: &dyn MessageDyn) -> Box<dyn MessageDyn> {
+        let mut ret = message.descriptor_dyn().new_instance();
+        project_message(message, ret.as_mut(), &self.cursor());
+        ret
+    }
+
+    pub fn cursor(&self) -> ProjectionCursor<'_> {
+        ProjectionCursor {
+            nodes: vec![&self.root],
+        }
+    }
+}
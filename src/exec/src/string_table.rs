@@ -1,18 +1,27 @@
 use std::any::Any;
 use std::collections::VecDeque;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
 use std::ops::Deref;
 use std::rc::Rc;
 
 use super::dump_options::DumpOptions;
 use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_key::RowKey;
 use super::utility;
 
 use protobuf::MessageDyn;
+use sha2::{Digest, Sha256};
+
+type StringTableSources = HashMap<String, HashSet<dump_plugin::DumpPluginItemDataSource>>;
 
 struct StringTableContent {
     pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
-    pub body: HashMap<String, HashSet<dump_plugin::DumpPluginItemDataSource>>,
+    pub body: StringTableSources,
+    pub occurrences: HashMap<String, Vec<StringTableOccurrence>>,
+    pub row_key: Option<RowKey>,
 }
 
 impl dump_plugin::DumpPluginBlockInterface for StringTableContent {
@@ -30,7 +39,7 @@ impl dump_plugin::DumpPluginBlockInterface for StringTableContent {
 }
 
 #[derive(Default)]
-struct StringTableFilter {
+pub struct StringTableFilter {
     pub value_include_regex_rules: Vec<regex::Regex>,
     pub value_exclude_regex_rules: Vec<regex::Regex>,
     pub include_message_paths: HashSet<String>,
@@ -104,12 +113,90 @@ impl StringTableFilter {
     }
 }
 
+/// Where a string is found, one string may be found in many fields and rows
+struct StringTableOccurrence {
+    /// Full name of the field, like `package.Message.field`
+    pub field_name: String,
+    pub field_path: String,
+    pub row_index: usize,
+    pub row_key: String,
+    pub source: Rc<dump_plugin::DumpPluginItemDataSource>,
+}
+
+/// Row of data block which is loading strings
+struct StringTableRow<'a> {
+    pub index: usize,
+    pub key: String,
+    pub data_source: &'a dump_plugin::DumpPluginSheetDataSource,
+}
+
+fn join_field_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
 impl StringTableContent {
-    pub fn load_message(
+    fn add_string(
+        &mut self,
+        value: &str,
+        field: &protobuf::reflect::FieldDescriptor,
+        field_path: String,
+        row: &StringTableRow,
+    ) {
+        let item_ds = &row.data_source.item;
+        let item = self.body.entry(value.to_string()).or_default();
+        if !item.contains(item_ds) {
+            item.insert(item_ds.as_ref().clone());
+        }
+
+        self.occurrences
+            .entry(value.to_string())
+            .or_default()
+            .push(StringTableOccurrence {
+                field_name: field.full_name(),
+                field_path,
+                row_index: row.index,
+                row_key: row.key.clone(),
+                source: item_ds.clone(),
+            });
+    }
+
+    fn load_value(
+        &mut self,
+        value: &protobuf::reflect::ReflectValueRef,
+        field: &protobuf::reflect::FieldDescriptor,
+        field_path: String,
+        filter: &StringTableFilter,
+        row: &StringTableRow,
+    ) {
+        match value {
+            protobuf::reflect::ReflectValueRef::Message(m) => {
+                self.load_message(m.deref(), filter, row, &field_path);
+            }
+            protobuf::reflect::ReflectValueRef::String(s) => {
+                if !filter.filter_field(field) {
+                    return;
+                }
+
+                if !filter.filter_value(s) {
+                    return;
+                }
+
+                self.add_string(s, field, field_path, row);
+            }
+            _ => {}
+        }
+    }
+
+    fn load_message(
         &mut self,
         message: &dyn MessageDyn,
         filter: &StringTableFilter,
-        data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row: &StringTableRow,
+        path_prefix: &str,
     ) {
         if !filter.filter_message(&message.descriptor_dyn()) {
             return;
@@ -121,33 +208,8 @@ impl StringTableContent {
             .for_each(|field| match field.runtime_field_type() {
                 protobuf::reflect::RuntimeFieldType::Singular(_) => {
                     if let Some(v) = field.get_singular(message) {
-                        match v {
-                            protobuf::reflect::ReflectValueRef::Message(m) => {
-                                self.load_message(m.deref(), filter, data_source);
-                            }
-                            protobuf::reflect::ReflectValueRef::String(s) => {
-                                if !filter.filter_field(&field) {
-                                    return;
-                                }
-
-                                if !filter.filter_value(s) {
-                                    return;
-                                }
-
-                                let value = v.to_string();
-                                if let Some(item) = self.body.get_mut(&value) {
-                                    let item_ds = &data_source.item;
-                                    if !item.contains(item_ds) {
-                                        item.insert(item_ds.as_ref().clone());
-                                    }
-                                } else {
-                                    let mut hs = HashSet::new();
-                                    hs.insert(data_source.item.as_ref().clone());
-                                    self.body.insert(value, hs);
-                                }
-                            }
-                            _ => {}
-                        }
+                        let field_path = join_field_path(path_prefix, field.name());
+                        self.load_value(&v, &field, field_path, filter, row);
                     }
                 }
                 protobuf::reflect::RuntimeFieldType::Repeated(_) => {
@@ -158,96 +220,45 @@ impl StringTableContent {
                     field
                         .get_repeated(message)
                         .into_iter()
-                        .for_each(|v| match v {
-                            protobuf::reflect::ReflectValueRef::Message(m) => {
-                                self.load_message(m.deref(), filter, data_source);
-                            }
-                            protobuf::reflect::ReflectValueRef::String(s) => {
-                                if !filter.filter_field(&field) {
-                                    return;
-                                }
-
-                                if !filter.filter_value(s) {
-                                    return;
-                                }
-
-                                let value = v.to_string();
-                                if let Some(item) = self.body.get_mut(&value) {
-                                    let item_ds = &data_source.item;
-                                    if !item.contains(item_ds) {
-                                        item.insert(item_ds.as_ref().clone());
-                                    }
-                                } else {
-                                    let mut hs = HashSet::new();
-                                    hs.insert(data_source.item.as_ref().clone());
-                                    self.body.insert(value, hs);
-                                }
-                            }
-                            _ => {}
+                        .enumerate()
+                        .for_each(|(index, v)| {
+                            let field_path = format!(
+                                "{}[{}]",
+                                join_field_path(path_prefix, field.name()),
+                                index
+                            );
+                            self.load_value(&v, &field, field_path, filter, row);
                         })
                 }
                 protobuf::reflect::RuntimeFieldType::Map(_, _) => {
                     field.get_map(message).into_iter().for_each(|(k, v)| {
-                        match k {
-                            protobuf::reflect::ReflectValueRef::Message(m) => {
-                                self.load_message(m.deref(), filter, data_source);
-                            }
-                            protobuf::reflect::ReflectValueRef::String(s) => {
-                                if !filter.filter_field(&field) {
-                                    return;
-                                }
-
-                                if !filter.filter_value(s) {
-                                    return;
-                                }
-
-                                let value = v.to_string();
-                                if let Some(item) = self.body.get_mut(&value) {
-                                    let item_ds = &data_source.item;
-                                    if !item.contains(item_ds) {
-                                        item.insert(item_ds.as_ref().clone());
-                                    }
-                                } else {
-                                    let mut hs = HashSet::new();
-                                    hs.insert(data_source.item.as_ref().clone());
-                                    self.body.insert(value, hs);
-                                }
-                            }
-                            _ => {}
-                        }
-
-                        match v {
-                            protobuf::reflect::ReflectValueRef::Message(m) => {
-                                self.load_message(m.deref(), filter, data_source);
-                            }
-                            protobuf::reflect::ReflectValueRef::String(s) => {
-                                if !filter.filter_field(&field) {
-                                    return;
-                                }
-
-                                if !filter.filter_value(s) {
-                                    return;
-                                }
-
-                                let value = v.to_string();
-                                if let Some(item) = self.body.get_mut(&value) {
-                                    let item_ds = &data_source.item;
-                                    if !item.contains(item_ds) {
-                                        item.insert(item_ds.as_ref().clone());
-                                    }
-                                } else {
-                                    let mut hs = HashSet::new();
-                                    hs.insert(data_source.item.as_ref().clone());
-                                    self.body.insert(value, hs);
-                                }
-                            }
-                            _ => {}
-                        }
+                        let field_path = format!(
+                            "{}[{}]",
+                            join_field_path(path_prefix, field.name()),
+                            utility::pb_value_to_string(&k)
+                        );
+                        self.load_value(&v, &field, field_path, filter, row);
                     });
                 }
             });
     }
 
+    /// Field path, row index and row key of every occurrence, in order of rows
+    fn occurrences_to_json(&self, value: &str) -> json::JsonValue {
+        let mut ret = json::JsonValue::new_array();
+        for occurrence in self.occurrences.get(value).into_iter().flatten() {
+            let mut d = json::JsonValue::new_object();
+            let _ = d.insert("field", occurrence.field_name.clone());
+            let _ = d.insert("field_path", occurrence.field_path.clone());
+            let _ = d.insert("row_index", occurrence.row_index);
+            let _ = d.insert("row_key", occurrence.row_key.clone());
+            let _ = d.insert("file", occurrence.source.file.clone());
+            let _ = d.insert("sheet", occurrence.source.sheet.clone());
+            let _ = ret.push(d);
+        }
+        ret
+    }
+
     pub fn to_json(
         &self,
         json_item_head: json::JsonValue,
@@ -279,6 +290,7 @@ impl StringTableContent {
                     },
                 );
                 let _ = body_item.insert("source", body_item_source);
+                let _ = body_item.insert("occurrences", self.occurrences_to_json(key));
                 let mut json_item_body_data = json::JsonValue::new_object();
                 let _ = json_item_body_data.insert(key, body_item);
                 let _ = json_item_body.push(json_item_body_data);
@@ -296,6 +308,7 @@ impl StringTableContent {
                     let _ = body_item_source.push(d);
                 }
                 let _ = body_item.insert("source", body_item_source);
+                let _ = body_item.insert("occurrences", self.occurrences_to_json(key));
                 let _ = json_item_body.insert(key, body_item);
             }
             let _ = json_item.insert("body", json_item_body);
@@ -314,7 +327,7 @@ impl StringTableContent {
     }
 }
 
-fn build_string_table_filter(args: &DumpOptions) -> (StringTableFilter, bool) {
+pub fn build_string_table_filter(args: &DumpOptions) -> (StringTableFilter, bool) {
     let mut ret: StringTableFilter = StringTableFilter::default();
     let mut has_error = false;
 
@@ -403,6 +416,621 @@ fn build_string_table_filter(args: &DumpOptions) -> (StringTableFilter, bool) {
     (ret, has_error)
 }
 
+fn load_string_table_baseline_sources(
+    json_sources: &json::JsonValue,
+    output: &mut HashSet<dump_plugin::DumpPluginItemDataSource>,
+) {
+    for source in json_sources.members() {
+        output.insert(dump_plugin::DumpPluginItemDataSource {
+            file: source["file"].as_str().unwrap_or_default().to_string(),
+            sheet: source["sheet"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+}
+
+/// Load string table json generated by --output-string-table-json, both ordered and unordered mode are supported
+fn load_string_table_baseline(file_path: &str) -> Result<StringTableSources, ()> {
+    let mut content = String::new();
+    match File::open(file_path) {
+        Ok(mut f) => {
+            if let Err(e) = f.read_to_string(&mut content) {
+                error!(
+                    "Try to read string table baseline {} failed, {}",
+                    file_path, e
+                );
+                return Err(());
+            }
+        }
+        Err(e) => {
+            error!(
+                "Try to open string table baseline {} failed, {}",
+                file_path, e
+            );
+            return Err(());
+        }
+    }
+
+    let json_root = match json::parse(&content) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Parse string table baseline {} failed, {}", file_path, e);
+            return Err(());
+        }
+    };
+
+    if !json_root.is_array() {
+        error!(
+            "Invalid string table baseline {}, it should be generated by --output-string-table-json",
+            file_path
+        );
+        return Err(());
+    }
+
+    let mut ret = StringTableSources::new();
+    for json_block in json_root.members() {
+        let json_body = &json_block["body"];
+        let mut load_item = |key: &str, value: &json::JsonValue| {
+            load_string_table_baseline_sources(
+                &value["source"],
+                ret.entry(key.to_string()).or_default(),
+            );
+        };
+
+        if json_body.is_array() {
+            for json_item in json_body.members() {
+                for (key, value) in json_item.entries() {
+                    load_item(key, value);
+                }
+            }
+        } else {
+            for (key, value) in json_body.entries() {
+                load_item(key, value);
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+fn string_table_sources_to_json(
+    sources: &HashSet<dump_plugin::DumpPluginItemDataSource>,
+) -> json::JsonValue {
+    let mut ret = json::JsonValue::new_array();
+    utility::for_each_ordered_hash_set_by(
+        sources,
+        |a, b| {
+            if a.file == b.file {
+                a.sheet.cmp(&b.sheet)
+            } else {
+                a.file.cmp(&b.file)
+            }
+        },
+        |source| {
+            let mut d = json::JsonValue::new_object();
+            let _ = d.insert("file", source.file.clone());
+            let _ = d.insert("sheet", source.sheet.clone());
+            let _ = ret.push(d);
+        },
+    );
+    ret
+}
+
+fn write_string_table_file(
+    output_file: &str,
+    content: &[u8],
+) -> dump_plugin::DumpPluginFlushResult {
+    match utility::create_output_file(output_file) {
+        Ok(mut f) => {
+            if let Err(e) = f.write_all(content) {
+                error!("Try to write string table to {} failed, {}", output_file, e);
+                return Err(());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!(
+                "Try to open {} to write string table failed, {}",
+                output_file, e
+            );
+            Err(())
+        }
+    }
+}
+
+/// Strings added, removed or with different sources relative to the baseline
+struct StringTableDelta<'a> {
+    pub added: Vec<(
+        &'a String,
+        &'a HashSet<dump_plugin::DumpPluginItemDataSource>,
+    )>,
+    pub removed: Vec<(
+        &'a String,
+        &'a HashSet<dump_plugin::DumpPluginItemDataSource>,
+    )>,
+    pub source_changed: Vec<(
+        &'a String,
+        &'a HashSet<dump_plugin::DumpPluginItemDataSource>,
+        &'a HashSet<dump_plugin::DumpPluginItemDataSource>,
+    )>,
+}
+
+impl<'a> StringTableDelta<'a> {
+    pub fn new(baseline: &'a StringTableSources, current: &'a StringTableSources) -> Self {
+        let mut ret = StringTableDelta {
+            added: Vec::new(),
+            removed: Vec::new(),
+            source_changed: Vec::new(),
+        };
+
+        for (key, sources) in current {
+            match baseline.get(key) {
+                Some(old_sources) => {
+                    if old_sources != sources {
+                        ret.source_changed.push((key, old_sources, sources));
+                    }
+                }
+                None => ret.added.push((key, sources)),
+            }
+        }
+
+        for (key, sources) in baseline {
+            if !current.contains_key(key) {
+                ret.removed.push((key, sources));
+            }
+        }
+
+        ret.added.sort_by(|a, b| a.0.cmp(b.0));
+        ret.removed.sort_by(|a, b| a.0.cmp(b.0));
+        ret.source_changed.sort_by(|a, b| a.0.cmp(b.0));
+        ret
+    }
+
+    pub fn to_json(&self) -> json::JsonValue {
+        let mut ret = json::JsonValue::new_object();
+
+        let mut summary = json::JsonValue::new_object();
+        let _ = summary.insert("added", self.added.len());
+        let _ = summary.insert("removed", self.removed.len());
+        let _ = summary.insert("source_changed", self.source_changed.len());
+        let _ = ret.insert("summary", summary);
+
+        for (name, items) in [("added", &self.added), ("removed", &self.removed)] {
+            let mut json_items = json::JsonValue::new_array();
+            for (key, sources) in items {
+                let mut json_item = json::JsonValue::new_object();
+                let _ = json_item.insert("value", key.as_str());
+                let _ = json_item.insert("source", string_table_sources_to_json(sources));
+                let _ = json_items.push(json_item);
+            }
+            let _ = ret.insert(name, json_items);
+        }
+
+        let mut json_items = json::JsonValue::new_array();
+        for (key, old_sources, new_sources) in &self.source_changed {
+            let mut json_item = json::JsonValue::new_object();
+            let _ = json_item.insert("value", key.as_str());
+            let _ = json_item.insert("old_source", string_table_sources_to_json(old_sources));
+            let _ = json_item.insert("source", string_table_sources_to_json(new_sources));
+            let _ = json_items.push(json_item);
+        }
+        let _ = ret.insert("source_changed", json_items);
+
+        ret
+    }
+
+    pub fn to_text(&self) -> Vec<String> {
+        let mut ret =
+            Vec::with_capacity(self.added.len() + self.removed.len() + self.source_changed.len());
+        for (key, _) in &self.added {
+            ret.push(format!("+ {}", key));
+        }
+        for (key, _) in &self.removed {
+            ret.push(format!("- {}", key));
+        }
+        for (key, _, _) in &self.source_changed {
+            ret.push(format!("~ {}", key));
+        }
+        ret
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum UeStringTableKeyMode {
+    /// Stable hash of source string, the same string share the same key
+    Hash,
+    /// Field path and row key of each occurrence
+    Path,
+    /// Running index of ordered strings
+    Index,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum UeStringTableEncoding {
+    Utf8,
+    Utf16Le,
+}
+
+struct UeStringTableEntry {
+    pub value: String,
+    pub sources: HashSet<dump_plugin::DumpPluginItemDataSource>,
+}
+
+/// Escape like FString::ReplaceCharWithEscapedChar of UE, and quote as a csv cell
+fn escape_ue_string_table_cell(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len() + 2);
+    ret.push('"');
+    for c in input.chars() {
+        match c {
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            '"' => ret.push_str("\"\""),
+            _ => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+fn get_ue_string_table_hash_key(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    digest[..16].iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn build_ue_string_table_entries(
+    blocks: &[&StringTableContent],
+    key_mode: UeStringTableKeyMode,
+    with_message_type: bool,
+) -> BTreeMap<String, UeStringTableEntry> {
+    let mut ret = BTreeMap::new();
+    if key_mode == UeStringTableKeyMode::Path {
+        for block in blocks {
+            for (value, occurrences) in &block.occurrences {
+                for occurrence in occurrences {
+                    let row_key = if occurrence.row_key.is_empty() {
+                        occurrence.row_index.to_string()
+                    } else {
+                        occurrence.row_key.clone()
+                    };
+                    let key = if with_message_type {
+                        format!(
+                            "{}:{}:{}",
+                            block.head.data_message_type, occurrence.field_path, row_key
+                        )
+                    } else {
+                        format!("{}:{}", occurrence.field_path, row_key)
+                    };
+
+                    let entry = ret.entry(key).or_insert_with_key(|_| UeStringTableEntry {
+                        value: value.clone(),
+                        sources: HashSet::new(),
+                    });
+                    if entry.value != *value {
+                        warn!(
+                            "Key {}:{} of UE StringTable is used by both \"{}\" and \"{}\", the later one is ignored",
+                            occurrence.field_path, row_key, entry.value, value
+                        );
+                        continue;
+                    }
+                    entry.sources.insert(occurrence.source.as_ref().clone());
+                }
+            }
+        }
+        return ret;
+    }
+
+    let mut values: BTreeMap<&String, HashSet<dump_plugin::DumpPluginItemDataSource>> =
+        BTreeMap::new();
+    for block in blocks {
+        for (value, sources) in &block.body {
+            values
+                .entry(value)
+                .or_default()
+                .extend(sources.iter().cloned());
+        }
+    }
+
+    for (index, (value, sources)) in values.into_iter().enumerate() {
+        let key = match key_mode {
+            UeStringTableKeyMode::Index => (index + 1).to_string(),
+            _ => get_ue_string_table_hash_key(value),
+        };
+        ret.insert(
+            key,
+            UeStringTableEntry {
+                value: value.clone(),
+                sources,
+            },
+        );
+    }
+    ret
+}
+
+/// Csv which can be imported by StringTable of UE, the columns are Key,SourceString[,Comment]
+fn ue_string_table_to_csv(
+    entries: &BTreeMap<String, UeStringTableEntry>,
+    with_comment: bool,
+) -> String {
+    let mut ret = String::new();
+    ret.push_str(if with_comment {
+        "Key,SourceString,Comment\n"
+    } else {
+        "Key,SourceString\n"
+    });
+
+    for (key, entry) in entries {
+        ret.push_str(&escape_ue_string_table_cell(key));
+        ret.push(',');
+        ret.push_str(&escape_ue_string_table_cell(&entry.value));
+        if with_comment {
+            let mut sources: Vec<String> = entry
+                .sources
+                .iter()
+                .map(|x| format!("{}:{}", x.file, x.sheet))
+                .collect();
+            sources.sort();
+            ret.push(',');
+            ret.push_str(&escape_ue_string_table_cell(&sources.join("; ")));
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+fn encode_ue_string_table(content: &str, encoding: UeStringTableEncoding) -> Vec<u8> {
+    match encoding {
+        UeStringTableEncoding::Utf8 => content.as_bytes().to_vec(),
+        UeStringTableEncoding::Utf16Le => {
+            let mut ret = Vec::with_capacity(content.len() * 2 + 2);
+            ret.extend_from_slice(&[0xff, 0xfe]);
+            for c in content.encode_utf16() {
+                ret.extend_from_slice(&c.to_le_bytes());
+            }
+            ret
+        }
+    }
+}
+
+/// Quote string of PO file, multi-line strings are split into lines after `\n`
+fn quote_po_string(input: &str) -> String {
+    let escape = |line: &str| {
+        let mut ret = String::with_capacity(line.len() + 2);
+        ret.push('"');
+        for c in line.chars() {
+            match c {
+                '\\' => ret.push_str("\\\\"),
+                '"' => ret.push_str("\\\""),
+                '\n' => ret.push_str("\\n"),
+                '\r' => ret.push_str("\\r"),
+                '\t' => ret.push_str("\\t"),
+                _ => ret.push(c),
+            }
+        }
+        ret.push('"');
+        ret
+    };
+
+    let lines: Vec<&str> = input.split_inclusive('\n').collect();
+    if lines.len() <= 1 {
+        return escape(input);
+    }
+
+    let mut ret = String::from("\"\"");
+    for line in lines {
+        ret.push('\n');
+        ret.push_str(&escape(line));
+    }
+    ret
+}
+
+/// Reference of `#:`, file names with spaces are isolated by FSI and PDI like gettext
+fn get_po_reference(source: &dump_plugin::DumpPluginItemDataSource) -> String {
+    let reference = format!("{}:{}", source.file, source.sheet);
+    if reference.contains(char::is_whitespace) {
+        format!("\u{2068}{}\u{2069}", reference)
+    } else {
+        reference
+    }
+}
+
+/// Strings of PO/POT, msgctxt is the full name of field
+fn string_table_to_po(
+    content: &VecDeque<Box<StringTableContent>>,
+    language: Option<&str>,
+) -> String {
+    let mut entries: BTreeMap<(&str, &str), HashSet<dump_plugin::DumpPluginItemDataSource>> =
+        BTreeMap::new();
+    for string_table in content {
+        for (value, occurrences) in &string_table.occurrences {
+            for occurrence in occurrences {
+                entries
+                    .entry((occurrence.field_name.as_str(), value.as_str()))
+                    .or_default()
+                    .insert(occurrence.source.as_ref().clone());
+            }
+        }
+    }
+
+    let mut ret =
+        String::from("# This file is generated by xresloader-dump-bin, please don't edit it.\n");
+    if language.is_none() {
+        ret.push_str("#, fuzzy\n");
+    }
+    ret.push_str("msgid \"\"\nmsgstr \"\"\n");
+    ret.push_str("\"MIME-Version: 1.0\\n\"\n");
+    ret.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    ret.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    if let Some(x) = language {
+        ret.push_str(&format!("\"Language: {}\\n\"\n", x));
+    }
+
+    for ((field_name, value), sources) in entries {
+        ret.push('\n');
+        let mut references: Vec<String> = sources.iter().map(get_po_reference).collect();
+        references.sort();
+        for reference in references {
+            ret.push_str(&format!("#: {}\n", reference));
+        }
+        ret.push_str(&format!("msgctxt {}\n", quote_po_string(field_name)));
+        ret.push_str(&format!("msgid {}\n", quote_po_string(value)));
+        ret.push_str("msgstr \"\"\n");
+    }
+    ret
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum XliffVersion {
+    V1_2,
+    V2_0,
+}
+
+/// Escape text and attributes of XML, characters not allowed in XML 1.0 are replaced
+fn escape_xml(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\t' | '\n' | '\r' => ret.push(c),
+            c if (c as u32) < 0x20 || c == '\u{fffe}' || c == '\u{ffff}' => ret.push('\u{fffd}'),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Field paths and sources of each string in a data block
+type XliffUnits<'a> = BTreeMap<&'a str, (BTreeSet<&'a str>, BTreeSet<String>)>;
+
+fn build_xliff_units(string_table: &StringTableContent) -> XliffUnits<'_> {
+    let mut ret = XliffUnits::new();
+    for (value, occurrences) in &string_table.occurrences {
+        let unit = ret.entry(value.as_str()).or_default();
+        for occurrence in occurrences {
+            unit.0.insert(occurrence.field_name.as_str());
+            unit.1.insert(format!(
+                "{}:{}",
+                occurrence.source.file, occurrence.source.sheet
+            ));
+        }
+    }
+    ret
+}
+
+/// One file for each binary file and one unit for each string, id of unit is the hash of string
+fn string_table_to_xliff(
+    content: &VecDeque<Box<StringTableContent>>,
+    version: XliffVersion,
+    source_language: &str,
+    target_language: &str,
+) -> String {
+    let mut ret = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let target_attribute = |name: &str| {
+        if target_language.is_empty() {
+            String::new()
+        } else {
+            format!(" {}=\"{}\"", name, escape_xml(target_language))
+        }
+    };
+
+    match version {
+        XliffVersion::V1_2 => ret.push_str(
+            "<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n",
+        ),
+        XliffVersion::V2_0 => ret.push_str(&format!(
+            "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{}\"{}>\n",
+            escape_xml(source_language),
+            target_attribute("trgLang")
+        )),
+    }
+
+    for (file_index, string_table) in content.iter().enumerate() {
+        let head = &string_table.head;
+        match version {
+            XliffVersion::V1_2 => {
+                ret.push_str(&format!(
+                    "  <file original=\"{}\" source-language=\"{}\"{} datatype=\"plaintext\" product-version=\"{}\">\n",
+                    escape_xml(&head.file_path),
+                    escape_xml(source_language),
+                    target_attribute("target-language"),
+                    escape_xml(&head.data_ver)
+                ));
+                ret.push_str("    <body>\n");
+            }
+            XliffVersion::V2_0 => {
+                ret.push_str(&format!(
+                    "  <file id=\"f{}\" original=\"{}\">\n",
+                    file_index + 1,
+                    escape_xml(&head.file_path)
+                ));
+                ret.push_str(&format!(
+                    "    <notes>\n      <note category=\"data_ver\">{}</note>\n      <note category=\"message_type\">{}</note>\n    </notes>\n",
+                    escape_xml(&head.data_ver),
+                    escape_xml(&head.data_message_type)
+                ));
+            }
+        }
+
+        for (value, (field_names, sources)) in build_xliff_units(string_table) {
+            let id = get_ue_string_table_hash_key(value);
+            let mut notes = String::new();
+            for (category, items) in [
+                ("field", field_names.iter().copied().collect::<Vec<&str>>()),
+                ("source", sources.iter().map(|x| x.as_str()).collect()),
+            ] {
+                for item in items {
+                    match version {
+                        XliffVersion::V1_2 => notes.push_str(&format!(
+                            "        <note from=\"{}\">{}</note>\n",
+                            category,
+                            escape_xml(item)
+                        )),
+                        XliffVersion::V2_0 => notes.push_str(&format!(
+                            "        <note category=\"{}\">{}</note>\n",
+                            category,
+                            escape_xml(item)
+                        )),
+                    }
+                }
+            }
+
+            match version {
+                XliffVersion::V1_2 => {
+                    ret.push_str(&format!(
+                        "      <trans-unit id=\"{}\" xml:space=\"preserve\">\n",
+                        id
+                    ));
+                    ret.push_str(&format!("        <source>{}</source>\n", escape_xml(value)));
+                    ret.push_str(&notes);
+                    ret.push_str("      </trans-unit>\n");
+                }
+                XliffVersion::V2_0 => {
+                    ret.push_str(&format!("    <unit id=\"{}\">\n", id));
+                    ret.push_str("      <notes>\n");
+                    ret.push_str(&notes);
+                    ret.push_str("      </notes>\n");
+                    ret.push_str(&format!(
+                        "      <segment>\n        <source xml:space=\"preserve\">{}</source>\n      </segment>\n",
+                        escape_xml(value)
+                    ));
+                    ret.push_str("    </unit>\n");
+                }
+            }
+        }
+
+        match version {
+            XliffVersion::V1_2 => ret.push_str("    </body>\n  </file>\n"),
+            XliffVersion::V2_0 => ret.push_str("  </file>\n"),
+        }
+    }
+
+    ret.push_str("</xliff>\n");
+    ret
+}
+
 pub struct DumpPluginStringTable {
     filter: StringTableFilter,
     content: VecDeque<Box<StringTableContent>>,
@@ -412,14 +1040,112 @@ pub struct DumpPluginStringTable {
     output_ordered: bool,
     write_to_text_file: String,
     write_to_json_file: String,
+
+    // delta output
+    baseline: Option<StringTableSources>,
+    write_delta_to_text_file: String,
+    write_delta_to_json_file: String,
+
+    // PO/POT output
+    write_po_to_file: String,
+    write_pot_to_file: String,
+    po_language: String,
+
+    // XLIFF output
+    write_xliff_to_file: String,
+    xliff_version: XliffVersion,
+    xliff_source_language: String,
+    xliff_target_language: String,
+
+    // UE StringTable output
+    write_ue_csv_to_path: String,
+    ue_key_mode: UeStringTableKeyMode,
+    ue_encoding: UeStringTableEncoding,
+    ue_with_comment: bool,
+    ue_namespace_by_message: bool,
 }
 
 impl DumpPluginStringTable {
-    pub fn build(args: &DumpOptions) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
-        if args.output_string_table_json.is_empty() && args.output_string_table_text.is_empty() {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        let has_delta_output = !args.output_string_table_delta_json.is_empty()
+            || !args.output_string_table_delta_text.is_empty();
+        if args.output_string_table_json.is_empty()
+            && args.output_string_table_text.is_empty()
+            && args.output_string_table_ue_csv.is_empty()
+            && args.output_string_table_po.is_empty()
+            && args.output_string_table_pot.is_empty()
+            && args.output_string_table_xliff.is_empty()
+            && !has_delta_output
+        {
             return (None, false);
         }
 
+        let ue_key_mode = match args.string_table_ue_key.to_lowercase().as_str() {
+            "hash" => UeStringTableKeyMode::Hash,
+            "path" => UeStringTableKeyMode::Path,
+            "index" => UeStringTableKeyMode::Index,
+            _ => {
+                error!(
+                    "Invalid --string-table-ue-key {}, it should be hash, path or index",
+                    args.string_table_ue_key
+                );
+                return (None, true);
+            }
+        };
+
+        let xliff_version = match args.string_table_xliff_version.as_str() {
+            "1.2" => XliffVersion::V1_2,
+            "2.0" | "2" => XliffVersion::V2_0,
+            _ => {
+                error!(
+                    "Invalid --string-table-xliff-version {}, it should be 1.2 or 2.0",
+                    args.string_table_xliff_version
+                );
+                return (None, true);
+            }
+        };
+
+        let ue_encoding = match args.string_table_ue_encoding.to_lowercase().as_str() {
+            "utf8" | "utf-8" => UeStringTableEncoding::Utf8,
+            "utf16le" | "utf-16le" => UeStringTableEncoding::Utf16Le,
+            _ => {
+                error!(
+                    "Invalid --string-table-ue-encoding {}, it should be utf8 or utf16le",
+                    args.string_table_ue_encoding
+                );
+                return (None, true);
+            }
+        };
+
+        if args.string_table_ue_namespace_by_message
+            && !args.output_string_table_ue_csv.is_empty()
+            && !utility::is_output_directory(&args.output_string_table_ue_csv)
+        {
+            error!(
+                "--output-string-table-ue-csv {} should be a directory when --string-table-ue-namespace-by-message is set",
+                args.output_string_table_ue_csv
+            );
+            return (None, true);
+        }
+
+        let baseline = if has_delta_output {
+            if args.string_table_baseline_json.is_empty() {
+                error!(
+                    "--string-table-baseline-json is required by --output-string-table-delta-json and --output-string-table-delta-text"
+                );
+                return (None, true);
+            }
+            match load_string_table_baseline(&args.string_table_baseline_json) {
+                Ok(x) => Some(x),
+                Err(_) => return (None, true),
+            }
+        } else {
+            None
+        };
+
         let (string_table_filter, has_string_table_error) = build_string_table_filter(&args);
         if has_string_table_error {
             return (None, has_string_table_error);
@@ -433,12 +1159,75 @@ impl DumpPluginStringTable {
                 output_ordered: args.string_table_ordered,
                 write_to_text_file: args.output_string_table_text.clone(),
                 write_to_json_file: args.output_string_table_json.clone(),
+                baseline,
+                write_delta_to_text_file: args.output_string_table_delta_text.clone(),
+                write_delta_to_json_file: args.output_string_table_delta_json.clone(),
+                write_po_to_file: args.output_string_table_po.clone(),
+                write_pot_to_file: args.output_string_table_pot.clone(),
+                po_language: args.string_table_po_language.clone(),
+                write_xliff_to_file: args.output_string_table_xliff.clone(),
+                xliff_version,
+                xliff_source_language: args.string_table_xliff_source_language.clone(),
+                xliff_target_language: args.string_table_xliff_target_language.clone(),
+                write_ue_csv_to_path: args.output_string_table_ue_csv.clone(),
+                ue_key_mode,
+                ue_encoding,
+                ue_with_comment: args.string_table_ue_comment,
+                ue_namespace_by_message: args.string_table_ue_namespace_by_message,
             })),
             false,
         )
     }
 }
 
+impl DumpPluginStringTable {
+    /// Write all strings into one StringTable, or one StringTable for each message type as namespace
+    fn dump_to_ue_csv(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut namespaces: BTreeMap<&str, Vec<&StringTableContent>> = BTreeMap::new();
+        for string_table in &self.content {
+            let namespace = if self.ue_namespace_by_message {
+                string_table.head.data_message_type.as_str()
+            } else {
+                ""
+            };
+            namespaces
+                .entry(namespace)
+                .or_default()
+                .push(string_table.as_ref());
+        }
+
+        if !self.ue_namespace_by_message && namespaces.is_empty() {
+            namespaces.insert("", Vec::new());
+        }
+
+        let mut ret = Ok(());
+        for (namespace, blocks) in namespaces {
+            let entries = build_ue_string_table_entries(
+                &blocks,
+                self.ue_key_mode,
+                !self.ue_namespace_by_message,
+            );
+            let content = encode_ue_string_table(
+                &ue_string_table_to_csv(&entries, self.ue_with_comment),
+                self.ue_encoding,
+            );
+            let output_file = if self.ue_namespace_by_message {
+                let mut path = std::path::PathBuf::from(&self.write_ue_csv_to_path);
+                path.push(format!("{}.csv", namespace));
+                path.to_string_lossy().to_string()
+            } else {
+                self.write_ue_csv_to_path.clone()
+            };
+
+            if let Err(e) = write_string_table_file(&output_file, &content) {
+                ret = Err(e);
+            }
+        }
+
+        ret
+    }
+}
+
 impl dump_plugin::DumpPluginInterface for DumpPluginStringTable {
     fn create_block(
         &self,
@@ -447,6 +1236,8 @@ impl dump_plugin::DumpPluginInterface for DumpPluginStringTable {
         Some(Box::new(StringTableContent {
             head: data_source,
             body: HashMap::new(),
+            occurrences: HashMap::new(),
+            row_key: None,
         }))
     }
 
@@ -455,9 +1246,18 @@ impl dump_plugin::DumpPluginInterface for DumpPluginStringTable {
         block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
         message: &dyn MessageDyn,
         data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
     ) {
         if let Some(rb) = block.as_any_mut().downcast_mut::<StringTableContent>() {
-            rb.load_message(message, &mut self.filter, &data_source);
+            let row_key = rb
+                .row_key
+                .get_or_insert_with(|| RowKey::from_descriptor(&message.descriptor_dyn()));
+            let row = StringTableRow {
+                index: row_index,
+                key: row_key.values(message).join("_"),
+                data_source,
+            };
+            rb.load_message(message, &self.filter, &row, "");
         } else {
             error!(
                 "In DumpPluginStringTable::load_message, the block is not StringTableContent, ignore this message"
@@ -516,6 +1316,77 @@ impl dump_plugin::DumpPluginInterface for DumpPluginStringTable {
             }
         }
 
+        if !self.write_pot_to_file.is_empty() {
+            let output = string_table_to_po(&self.content, None);
+            if let Err(e) = write_string_table_file(&self.write_pot_to_file, output.as_bytes()) {
+                ret = Err(e);
+            }
+        }
+
+        if !self.write_po_to_file.is_empty() {
+            let output = string_table_to_po(&self.content, Some(&self.po_language));
+            if let Err(e) = write_string_table_file(&self.write_po_to_file, output.as_bytes()) {
+                ret = Err(e);
+            }
+        }
+
+        if !self.write_xliff_to_file.is_empty() {
+            let output = string_table_to_xliff(
+                &self.content,
+                self.xliff_version,
+                &self.xliff_source_language,
+                &self.xliff_target_language,
+            );
+            if let Err(e) = write_string_table_file(&self.write_xliff_to_file, output.as_bytes()) {
+                ret = Err(e);
+            }
+        }
+
+        if !self.write_ue_csv_to_path.is_empty()
+            && let Err(e) = self.dump_to_ue_csv()
+        {
+            ret = Err(e);
+        }
+
+        if let Some(baseline) = &self.baseline {
+            let mut current = StringTableSources::new();
+            for string_table in &self.content {
+                for (key, sources) in &string_table.body {
+                    current
+                        .entry(key.clone())
+                        .or_default()
+                        .extend(sources.iter().cloned());
+                }
+            }
+
+            let delta = StringTableDelta::new(baseline, &current);
+            if !self.write_delta_to_text_file.is_empty() {
+                let mut output = String::new();
+                for line in delta.to_text() {
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+                if let Err(e) =
+                    write_string_table_file(&self.write_delta_to_text_file, output.as_bytes())
+                {
+                    ret = Err(e);
+                }
+            }
+
+            if !self.write_delta_to_json_file.is_empty() {
+                let output = if self.output_pretty {
+                    json::stringify_pretty(delta.to_json(), 2)
+                } else {
+                    json::stringify(delta.to_json())
+                };
+                if let Err(e) =
+                    write_string_table_file(&self.write_delta_to_json_file, output.as_bytes())
+                {
+                    ret = Err(e);
+                }
+            }
+        }
+
         ret
     }
 }
@@ -7,6 +7,7 @@ use std::rc::Rc;
 
 use super::dump_options::DumpOptions;
 use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
 use super::utility;
 
 use protobuf::reflect::ReflectValueRef;
@@ -551,7 +552,10 @@ pub struct DumpPluginTaggedField {
 }
 
 impl DumpPluginTaggedField {
-    pub fn build(args: &DumpOptions) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
         if args.output_tagged_data_json.is_empty() && args.output_tagged_data_text.is_empty() {
             return (None, false);
         }
@@ -591,6 +595,7 @@ impl dump_plugin::DumpPluginInterface for DumpPluginTaggedField {
         block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
         message: &dyn MessageDyn,
         data_source: &dump_plugin::DumpPluginSheetDataSource,
+        _row_index: usize,
     ) {
         if let Some(rb) = block.as_any_mut().downcast_mut::<TaggedFieldContent>() {
             rb.load_message(message, &mut self.filter, &data_source);
@@ -0,0 +1,563 @@
+use std::any::Any;
+use std::collections::{HashSet, VecDeque};
+use std::io::Write;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::csv_table;
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_flatten;
+use super::utility;
+
+use protobuf::MessageDyn;
+use protobuf::reflect::{FieldDescriptor, ReflectFieldRef, ReflectValueRef, RuntimeFieldType};
+use xresloader_protocol::proto::xresloader_ue::exts;
+
+/// Column and key of row name in DataTable of Unreal Engine
+const ROW_NAME_KEY: &str = "Name";
+
+#[derive(Clone, Copy, PartialEq)]
+enum UeDataTableFormat {
+    Csv,
+    Json,
+}
+
+struct UeDataTableContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    /// Decided by not_data_table of message when the first row is loaded
+    pub is_data_table: Option<bool>,
+    pub key_fields: Vec<(FieldDescriptor, i64)>,
+    pub row_names: HashSet<String>,
+    pub header: Vec<String>,
+    pub csv_rows: Vec<Vec<String>>,
+    pub json_rows: json::JsonValue,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for UeDataTableContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Property name of UE struct generated by xresloader, `item_id` => `ItemId`
+fn get_ue_property_name(field: &FieldDescriptor) -> String {
+    let mut ret = String::with_capacity(field.name().len());
+    for part in field.name().split('_') {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            ret.extend(c.to_uppercase());
+            ret.push_str(chars.as_str());
+        }
+    }
+
+    ret
+}
+
+fn is_not_data_table(message: &dyn MessageDyn) -> bool {
+    message
+        .descriptor_dyn()
+        .proto()
+        .options
+        .as_ref()
+        .and_then(|options| exts::not_data_table.get(options))
+        .unwrap_or(false)
+}
+
+/// Fields with key_tag > 0 are used to generate row name
+fn get_key_fields(message: &dyn MessageDyn) -> Vec<(FieldDescriptor, i64)> {
+    message
+        .descriptor_dyn()
+        .fields()
+        .filter(|field| matches!(field.runtime_field_type(), RuntimeFieldType::Singular(_)))
+        .filter_map(|field| {
+            let key_tag = field
+                .proto()
+                .options
+                .as_ref()
+                .and_then(|options| exts::key_tag.get(options))
+                .unwrap_or(0);
+            if key_tag > 0 {
+                Some((field, key_tag))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn get_integer_value(value: &ReflectValueRef) -> Option<i64> {
+    match value {
+        ReflectValueRef::U32(x) => Some(*x as i64),
+        ReflectValueRef::U64(x) => Some(*x as i64),
+        ReflectValueRef::I32(x) => Some(*x as i64),
+        ReflectValueRef::I64(x) => Some(*x),
+        ReflectValueRef::Enum(_, x) => Some(*x as i64),
+        _ => None,
+    }
+}
+
+/// Row name is sum of key field * key_tag like the GetRowName of xresloader, or row index if there is no key_tag
+fn get_row_name(
+    message: &dyn MessageDyn,
+    key_fields: &[(FieldDescriptor, i64)],
+    row_index: usize,
+) -> String {
+    if key_fields.is_empty() {
+        return row_index.to_string();
+    }
+
+    let values: Vec<ReflectValueRef> = key_fields
+        .iter()
+        .map(|(field, _)| field.get_singular_field_or_default(message))
+        .collect();
+    if values.iter().all(|x| get_integer_value(x).is_some()) {
+        let mut ret: i64 = 0;
+        for ((_, key_tag), value) in key_fields.iter().zip(values.iter()) {
+            ret = ret.wrapping_add(get_integer_value(value).unwrap_or(0).wrapping_mul(*key_tag));
+        }
+        return ret.to_string();
+    }
+
+    values
+        .iter()
+        .map(utility::pb_value_to_string)
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+/// Strings with ue_type_name are soft references of assets, written as `Type'/Game/Path.Asset'`
+fn get_object_reference(field: &FieldDescriptor, path: &str) -> Option<String> {
+    let options = field.proto().options.as_ref()?;
+    let type_name = exts::ue_type_name.get(options).filter(|x| !x.is_empty())?;
+    if path.is_empty() || path.contains('\'') {
+        return Some(path.to_string());
+    }
+
+    if exts::ue_type_is_class.get(options).unwrap_or(false) {
+        return Some(format!("Class'{}'", path));
+    }
+
+    // UTexture2D => Texture2D, AActor => Actor
+    let mut chars = type_name.chars();
+    let class_name = match (chars.next(), chars.next()) {
+        (Some('U' | 'A'), Some(c)) if c.is_ascii_uppercase() => &type_name[1..],
+        _ => type_name.as_str(),
+    };
+    Some(format!("{}'{}'", class_name, path))
+}
+
+/// Value of ue_origin_type_default_value is used for unset fields
+fn get_default_value_text(field: &FieldDescriptor, message: &dyn MessageDyn) -> Option<String> {
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(v) if v.value().is_none() => {}
+        _ => return None,
+    }
+
+    field
+        .proto()
+        .options
+        .as_ref()
+        .and_then(|options| exts::ue_origin_type_default_value.get(options))
+        .filter(|x| !x.is_empty())
+}
+
+fn get_string_value(field: &FieldDescriptor, value: &ReflectValueRef) -> Option<String> {
+    let ret = match value {
+        ReflectValueRef::String(x) => x.to_string(),
+        ReflectValueRef::Bytes(x) => String::from_utf8_lossy(x).to_string(),
+        _ => return None,
+    };
+
+    Some(get_object_reference(field, &ret).unwrap_or(ret))
+}
+
+fn quote_ue_text(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len() + 2);
+    ret.push('"');
+    for c in input.chars() {
+        match c {
+            '\\' => ret.push_str("\\\\"),
+            '"' => ret.push_str("\\\""),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Value in format of ExportText of UE, strings in structs and containers are quoted
+fn value_to_ue_text(field: &FieldDescriptor, value: &ReflectValueRef, nested: bool) -> String {
+    if let Some(x) = get_string_value(field, value) {
+        return if nested { quote_ue_text(&x) } else { x };
+    }
+
+    match value {
+        ReflectValueRef::Bool(x) => if *x { "True" } else { "False" }.to_string(),
+        ReflectValueRef::Enum(e, x) => match e.value_by_number(*x) {
+            Some(v) => v.name().to_string(),
+            None => x.to_string(),
+        },
+        ReflectValueRef::Message(m) => message_to_ue_text(m.deref()),
+        _ => utility::pb_value_to_string(value),
+    }
+}
+
+fn field_to_ue_text(field: &FieldDescriptor, message: &dyn MessageDyn, nested: bool) -> String {
+    if let Some(x) = get_default_value_text(field, message) {
+        return x;
+    }
+
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(v) => match v.value() {
+            Some(x) => value_to_ue_text(field, &x, nested),
+            None => value_to_ue_text(field, &field.get_singular_field_or_default(message), nested),
+        },
+        ReflectFieldRef::Repeated(v) => format!(
+            "({})",
+            v.into_iter()
+                .map(|x| value_to_ue_text(field, &x, true))
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+        ReflectFieldRef::Map(v) => {
+            let mut entries: Vec<(ReflectValueRef, ReflectValueRef)> = (&v).into_iter().collect();
+            // Keep stable order of map
+            entries.sort_by_key(|(k, _)| row_flatten::map_key_to_flatten_key(k));
+            format!(
+                "({})",
+                entries
+                    .iter()
+                    .map(|(k, x)| format!(
+                        "({},{})",
+                        value_to_ue_text(field, k, true),
+                        value_to_ue_text(field, x, true)
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )
+        }
+    }
+}
+
+fn message_to_ue_text(message: &dyn MessageDyn) -> String {
+    format!(
+        "({})",
+        message
+            .descriptor_dyn()
+            .fields()
+            .map(|field| format!(
+                "{}={}",
+                get_ue_property_name(&field),
+                field_to_ue_text(&field, message, true)
+            ))
+            .collect::<Vec<String>>()
+            .join(",")
+    )
+}
+
+fn value_to_ue_json(field: &FieldDescriptor, value: &ReflectValueRef) -> json::JsonValue {
+    if let Some(x) = get_string_value(field, value) {
+        return json::JsonValue::from(x);
+    }
+
+    match value {
+        ReflectValueRef::U32(x) => json::JsonValue::from(*x),
+        ReflectValueRef::U64(x) => json::JsonValue::from(*x),
+        ReflectValueRef::I32(x) => json::JsonValue::from(*x),
+        ReflectValueRef::I64(x) => json::JsonValue::from(*x),
+        ReflectValueRef::F32(x) => json::JsonValue::from(*x),
+        ReflectValueRef::F64(x) => json::JsonValue::from(*x),
+        ReflectValueRef::Bool(x) => json::JsonValue::from(*x),
+        ReflectValueRef::Message(m) => message_to_ue_json(m.deref()),
+        _ => json::JsonValue::from(utility::pb_value_to_string(value)),
+    }
+}
+
+fn field_to_ue_json(field: &FieldDescriptor, message: &dyn MessageDyn) -> json::JsonValue {
+    if let Some(x) = get_default_value_text(field, message) {
+        return json::JsonValue::from(x);
+    }
+
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(v) => match v.value() {
+            Some(x) => value_to_ue_json(field, &x),
+            None => value_to_ue_json(field, &field.get_singular_field_or_default(message)),
+        },
+        ReflectFieldRef::Repeated(v) => {
+            let mut ret = json::JsonValue::new_array();
+            for x in v {
+                let _ = ret.push(value_to_ue_json(field, &x));
+            }
+            ret
+        }
+        ReflectFieldRef::Map(v) => {
+            let mut entries: Vec<(ReflectValueRef, ReflectValueRef)> = (&v).into_iter().collect();
+            // Keep stable order of map
+            entries.sort_by_key(|(k, _)| row_flatten::map_key_to_flatten_key(k));
+            let mut ret = json::JsonValue::new_object();
+            for (k, x) in entries {
+                ret[utility::pb_value_to_string(&k)] = value_to_ue_json(field, &x);
+            }
+            ret
+        }
+    }
+}
+
+fn message_to_ue_json(message: &dyn MessageDyn) -> json::JsonValue {
+    let mut ret = json::JsonValue::new_object();
+    for field in message.descriptor_dyn().fields() {
+        ret[get_ue_property_name(&field)] = field_to_ue_json(&field, message);
+    }
+    ret
+}
+
+impl UeDataTableContent {
+    fn to_csv(&self) -> String {
+        let mut ret = String::new();
+        if self.header.is_empty() {
+            ret.push_str(ROW_NAME_KEY);
+            ret.push('\n');
+        }
+        for row in std::iter::once(&self.header)
+            .filter(|x| !x.is_empty())
+            .chain(self.csv_rows.iter())
+        {
+            ret.push_str(
+                &row.iter()
+                    .map(|x| csv_table::escape_csv_cell(x, ','))
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+/// Rows written as DataTable of Unreal Engine, which can be imported from csv or json
+pub struct DumpPluginUeDataTable {
+    content: VecDeque<Box<UeDataTableContent>>,
+
+    // output
+    format: UeDataTableFormat,
+    write_to_path: String,
+}
+
+impl DumpPluginUeDataTable {
+    pub fn build_csv(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_ue_csv.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(&args.output_ue_csv, "--output-ue-csv", args.bin_file.len())
+        {
+            return (None, true);
+        }
+
+        (
+            Some(Box::new(DumpPluginUeDataTable {
+                content: VecDeque::new(),
+                format: UeDataTableFormat::Csv,
+                write_to_path: args.output_ue_csv.clone(),
+            })),
+            false,
+        )
+    }
+
+    pub fn build_json(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_ue_json.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(
+            &args.output_ue_json,
+            "--output-ue-json",
+            args.bin_file.len(),
+        ) {
+            return (None, true);
+        }
+
+        (
+            Some(Box::new(DumpPluginUeDataTable {
+                content: VecDeque::new(),
+                format: UeDataTableFormat::Json,
+                write_to_path: args.output_ue_json.clone(),
+            })),
+            false,
+        )
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginUeDataTable {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(UeDataTableContent {
+            head: data_source,
+            is_data_table: None,
+            key_fields: Vec::new(),
+            row_names: HashSet::new(),
+            header: Vec::new(),
+            csv_rows: Vec::new(),
+            json_rows: json::JsonValue::new_array(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        _data_source: &dump_plugin::DumpPluginSheetDataSource,
+        row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<UeDataTableContent>() {
+            if rb.is_data_table.is_none() {
+                if is_not_data_table(message) {
+                    warn!(
+                        "Message {} of file {} is marked not_data_table, skip exporting it as UE DataTable",
+                        message.descriptor_dyn().full_name(),
+                        rb.head.file_path
+                    );
+                    rb.is_data_table = Some(false);
+                } else {
+                    rb.is_data_table = Some(true);
+                    rb.key_fields = get_key_fields(message);
+                    if let Some(field) = message
+                        .descriptor_dyn()
+                        .fields()
+                        .find(|x| get_ue_property_name(x) == ROW_NAME_KEY)
+                    {
+                        warn!(
+                            "Field {} of {} in file {} conflicts with row name of UE DataTable, it will be skipped",
+                            field.name(),
+                            message.descriptor_dyn().full_name(),
+                            rb.head.file_path
+                        );
+                    }
+                    rb.header.push(ROW_NAME_KEY.to_string());
+                    for field in message.descriptor_dyn().fields() {
+                        let property_name = get_ue_property_name(&field);
+                        if property_name != ROW_NAME_KEY {
+                            rb.header.push(property_name);
+                        }
+                    }
+                }
+            }
+            if rb.is_data_table != Some(true) {
+                return;
+            }
+
+            let row_name = get_row_name(message, &rb.key_fields, row_index);
+            if !rb.row_names.insert(row_name.clone()) {
+                warn!(
+                    "Row name {} of row {} in file {} is duplicated, it will override the previous one when imported into UE",
+                    row_name, row_index, rb.head.file_path
+                );
+            }
+
+            match self.format {
+                UeDataTableFormat::Csv => {
+                    let mut row = vec![row_name];
+                    for field in message.descriptor_dyn().fields() {
+                        if get_ue_property_name(&field) != ROW_NAME_KEY {
+                            row.push(field_to_ue_text(&field, message, false));
+                        }
+                    }
+                    rb.csv_rows.push(row);
+                }
+                UeDataTableFormat::Json => {
+                    let mut row = json::JsonValue::new_object();
+                    row[ROW_NAME_KEY] = json::JsonValue::from(row_name);
+                    for field in message.descriptor_dyn().fields() {
+                        let property_name = get_ue_property_name(&field);
+                        if property_name != ROW_NAME_KEY {
+                            row[property_name] = field_to_ue_json(&field, message);
+                        }
+                    }
+                    let _ = rb.json_rows.push(row);
+                }
+            }
+        } else {
+            error!(
+                "In DumpPluginUeDataTable::load_message, the block is not UeDataTableContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(rb) = block.into_any().downcast::<UeDataTableContent>() {
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginUeDataTable::push_block, the block is not UeDataTableContent, ignore this message"
+            );
+        }
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        Vec::new()
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = Ok(());
+        for data_table in &self.content {
+            if data_table.is_data_table == Some(false) {
+                continue;
+            }
+
+            let (extension, text) = match self.format {
+                UeDataTableFormat::Csv => ("csv", data_table.to_csv()),
+                UeDataTableFormat::Json => (
+                    "json",
+                    json::stringify_pretty(data_table.json_rows.clone(), 4),
+                ),
+            };
+            let output_file = utility::get_output_file_path(
+                &self.write_to_path,
+                &data_table.head.file_path,
+                extension,
+            );
+            match utility::create_output_file(&output_file) {
+                Ok(mut f) => {
+                    if let Err(e) = f.write_all(text.as_bytes()) {
+                        error!("Try to write UE DataTable to {} failed, {}", output_file, e);
+                        ret = Err(());
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Try to open {} to write UE DataTable failed, {}",
+                        output_file, e
+                    );
+                    ret = Err(());
+                }
+            }
+        }
+
+        ret
+    }
+}
@@ -3,6 +3,9 @@ use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::io::{BufRead, BufReader};
 
+use protobuf::MessageDyn;
+use protobuf::reflect::{FieldDescriptor, MessageDescriptor, ReflectFieldRef, ReflectValueRef};
+
 pub fn load_file_by_lines<P>(file_path: &str, file_type: &str, has_error: &mut bool, func: P)
 where
     P: FnMut(&str) -> Result<(), String>,
@@ -117,3 +120,143 @@ where
         func(item);
     }
 }
+
+pub fn pb_value_to_string(v: &ReflectValueRef) -> String {
+    match v {
+        ReflectValueRef::U32(x) => x.to_string(),
+        ReflectValueRef::U64(x) => x.to_string(),
+        ReflectValueRef::I32(x) => x.to_string(),
+        ReflectValueRef::I64(x) => x.to_string(),
+        ReflectValueRef::F32(x) => x.to_string(),
+        ReflectValueRef::F64(x) => x.to_string(),
+        ReflectValueRef::Bool(x) => x.to_string(),
+        ReflectValueRef::String(s) => s.to_string(),
+        ReflectValueRef::Message(m) => m.to_string(),
+        ReflectValueRef::Enum(e, i) => match e.value_by_number(*i) {
+            Some(x) => x.name().to_string(),
+            None => i.to_string(),
+        },
+        ReflectValueRef::Bytes(b) => format!("{:?}", b),
+    }
+}
+
+/// Repeated extensions are not supported by rust-protobuf v3, so we read them from unknown fields
+pub fn get_repeated_string_option(
+    unknown_fields: &protobuf::UnknownFields,
+    field_number: u32,
+) -> Vec<String> {
+    let mut ret = Vec::new();
+    for (k, v) in unknown_fields.iter() {
+        if k != field_number {
+            continue;
+        }
+
+        if let protobuf::UnknownValueRef::LengthDelimited(value) = v {
+            match String::from_utf8(value.to_vec()) {
+                Ok(x) => ret.push(x),
+                Err(e) => {
+                    error!(
+                        "Failed to parse option {} as string, maybe corrupted data, {}",
+                        field_number, e
+                    );
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+/// Empty value means default value of scalar types or message without any field set
+pub fn is_pb_value_empty(v: &ReflectValueRef) -> bool {
+    match v {
+        ReflectValueRef::U32(x) => *x == 0,
+        ReflectValueRef::U64(x) => *x == 0,
+        ReflectValueRef::I32(x) => *x == 0,
+        ReflectValueRef::I64(x) => *x == 0,
+        ReflectValueRef::F32(x) => *x == 0.0,
+        ReflectValueRef::F64(x) => *x == 0.0,
+        ReflectValueRef::Bool(x) => !*x,
+        ReflectValueRef::String(x) => x.is_empty(),
+        ReflectValueRef::Bytes(x) => x.is_empty(),
+        ReflectValueRef::Enum(_, x) => *x == 0,
+        ReflectValueRef::Message(m) => m.compute_size_dyn() == 0,
+    }
+}
+
+pub fn pb_field_value_to_string(field: &FieldDescriptor, message: &dyn MessageDyn) -> String {
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(v) => match v.value() {
+            Some(x) => pb_value_to_string(&x),
+            None => pb_value_to_string(&field.get_singular_field_or_default(message)),
+        },
+        ReflectFieldRef::Repeated(v) => format!(
+            "[{}]",
+            v.into_iter()
+                .map(|x| pb_value_to_string(&x))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        ReflectFieldRef::Map(v) => format!(
+            "{{{}}}",
+            (&v).into_iter()
+                .map(|(k, x)| format!("{}: {}", pb_value_to_string(&k), pb_value_to_string(&x)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Find field by name, json name or name ignoring ASCII case
+pub fn find_message_field(message_desc: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+    if let Some(x) = message_desc.field_by_name(name) {
+        return Some(x);
+    }
+
+    message_desc
+        .fields()
+        .find(|field| field.json_name() == name || field.name().eq_ignore_ascii_case(name))
+}
+
+/// Outputs of each binary file can be written into a directory(exists or ends with '/'), or a file when there is only one binary file
+pub fn is_output_directory(output: &str) -> bool {
+    output.ends_with('/') || output.ends_with('\\') || std::path::Path::new(output).is_dir()
+}
+
+/// Check output path of per binary file outputs, print error and return false if it's not available
+pub fn check_output_path(output: &str, option_name: &str, bin_file_count: usize) -> bool {
+    if output.is_empty() || bin_file_count <= 1 || is_output_directory(output) {
+        return true;
+    }
+
+    error!(
+        "{} {} is a file but there are {} binary files, please use a directory",
+        option_name, output, bin_file_count
+    );
+    false
+}
+
+/// Get output file path of a binary file, `<output>/<name of bin_file>.<extension>` if output is a directory
+pub fn get_output_file_path(output: &str, bin_file: &str, extension: &str) -> String {
+    if !is_output_directory(output) {
+        return output.to_string();
+    }
+
+    let file_stem = std::path::Path::new(bin_file)
+        .file_stem()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_else(|| bin_file.to_string());
+    let mut ret = std::path::PathBuf::from(output);
+    ret.push(format!("{}.{}", file_stem, extension));
+    ret.to_string_lossy().to_string()
+}
+
+/// Create parent directories and open file to write
+pub fn create_output_file(output_file: &str) -> std::io::Result<std::fs::File> {
+    if let Some(parent) = std::path::Path::new(output_file).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::File::create(output_file)
+}
@@ -0,0 +1,358 @@
+use std::any::Any;
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+
+use super::dump_options::DumpOptions;
+use super::dump_plugin;
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::row_flatten::{
+    self, FlattenColumn, FlattenColumnKey, FlattenMapKey, FlattenPathPart, FlattenTable,
+};
+use super::row_projection::RowProjection;
+use super::utility;
+
+use protobuf::reflect::{FieldDescriptor, ReflectValueBox};
+use protobuf::{Message, MessageDyn};
+use rust_xlsxwriter::{Format, Note, Workbook, Worksheet, XlsxError};
+use xresloader_protocol::proto::xresloader::exts;
+
+// field_alias.field_number is private, see FIELD_UNIQUE_TAG_NUMBER
+const FIELD_ALIAS_NUMBER: u32 = 1012;
+
+/// Max length of sheet name in Excel
+const SHEET_NAME_MAX_LENGTH: usize = 31;
+
+/// Max column count of a sheet in Excel
+const MAX_COLUMN_COUNT: usize = 16384;
+
+/// Integers larger than this can not be stored in number cells without losing precision
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+struct XlsxTableSheet {
+    pub data_source: Rc<dump_plugin::DumpPluginItemDataSource>,
+    pub table: FlattenTable,
+}
+
+struct XlsxTableContent {
+    pub head: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    pub sheets: Vec<XlsxTableSheet>,
+}
+
+impl dump_plugin::DumpPluginBlockInterface for XlsxTableContent {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+fn get_field_header_name(field: &FieldDescriptor) -> String {
+    if let Some(options) = field.proto().options.as_ref()
+        && let Some(alias) =
+            utility::get_repeated_string_option(options.unknown_fields(), FIELD_ALIAS_NUMBER)
+                .into_iter()
+                .find(|x| !x.is_empty())
+    {
+        return alias;
+    }
+
+    field.name().to_string()
+}
+
+fn get_field_description(field: &FieldDescriptor) -> Option<String> {
+    field
+        .proto()
+        .options
+        .as_ref()
+        .and_then(|options| exts::field_description.get(options))
+        .filter(|x| !x.is_empty())
+}
+
+/// Header of column, the first field_alias of each field is used instead of field name if it's set
+fn get_column_header(key: &FlattenColumnKey, column: &FlattenColumn) -> String {
+    let mut ret = String::new();
+    let mut fields = column.fields.iter();
+    for part in key {
+        match part {
+            FlattenPathPart::Field(_) => {
+                if !ret.is_empty() {
+                    ret.push('.');
+                }
+                if let Some(field) = fields.next() {
+                    ret.push_str(&get_field_header_name(field));
+                }
+            }
+            FlattenPathPart::Index(index) => ret.push_str(&format!("[{}]", index)),
+            FlattenPathPart::Key(FlattenMapKey::Int(x)) => ret.push_str(&format!("[{}]", x)),
+            FlattenPathPart::Key(FlattenMapKey::String(x)) => ret.push_str(&format!("[{}]", x)),
+        }
+    }
+
+    ret
+}
+
+/// Replace characters not allowed in sheet name and make it unique in workbook
+fn get_sheet_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    let mut base_name: String = name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            _ => c,
+        })
+        .collect();
+    base_name = base_name.trim_matches('\'').to_string();
+    if base_name.is_empty() {
+        base_name = "Sheet".to_string();
+    }
+
+    let mut index = 1;
+    loop {
+        let suffix = if index > 1 {
+            format!("_{}", index)
+        } else {
+            String::new()
+        };
+        let mut ret: String = base_name
+            .chars()
+            .take(SHEET_NAME_MAX_LENGTH - suffix.chars().count())
+            .collect();
+        ret.push_str(&suffix);
+        if used_names.insert(ret.to_lowercase()) {
+            return ret;
+        }
+        index += 1;
+    }
+}
+
+fn write_cell(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    value: &ReflectValueBox,
+) -> Result<(), XlsxError> {
+    match value {
+        ReflectValueBox::I32(x) => worksheet.write_number(row, col, *x)?,
+        ReflectValueBox::U32(x) => worksheet.write_number(row, col, *x)?,
+        ReflectValueBox::F32(x) => worksheet.write_number(row, col, *x)?,
+        ReflectValueBox::F64(x) => worksheet.write_number(row, col, *x)?,
+        ReflectValueBox::I64(x) if x.unsigned_abs() <= MAX_SAFE_INTEGER => {
+            worksheet.write_number(row, col, *x as f64)?
+        }
+        ReflectValueBox::U64(x) if *x <= MAX_SAFE_INTEGER => {
+            worksheet.write_number(row, col, *x as f64)?
+        }
+        ReflectValueBox::Bool(x) => worksheet.write_boolean(row, col, *x)?,
+        _ => worksheet.write_string(row, col, row_flatten::flatten_value_to_string(value))?,
+    };
+
+    Ok(())
+}
+
+fn write_sheet(
+    worksheet: &mut Worksheet,
+    sheet_name: &str,
+    table: &FlattenTable,
+    header_format: &Format,
+) -> Result<(), XlsxError> {
+    worksheet.set_name(sheet_name)?;
+    if table.column_count() > MAX_COLUMN_COUNT {
+        return Err(XlsxError::RowColumnLimitError);
+    }
+
+    for (col, (key, column)) in table.columns().enumerate() {
+        let col = col as u16;
+        worksheet.write_string_with_format(
+            0,
+            col,
+            get_column_header(key, column),
+            header_format,
+        )?;
+        if let Some(description) = column.fields.last().and_then(get_field_description) {
+            worksheet.insert_note(0, col, &Note::new(description).add_author_prefix(false))?;
+        }
+
+        for row in 0..table.row_count() {
+            if let Some(value) = table.get(row, key) {
+                write_cell(worksheet, row as u32 + 1, col, value)?;
+            }
+        }
+    }
+
+    worksheet.set_freeze_panes(1, 0)?;
+    worksheet.autofit();
+    Ok(())
+}
+
+pub struct DumpPluginXlsxTable {
+    content: VecDeque<Box<XlsxTableContent>>,
+    /// Rows are received without projection and flattened by --select, so indexes of repeated fields are kept
+    projection: Option<RowProjection>,
+
+    // output
+    single_sheet: bool,
+    write_to_path: String,
+}
+
+impl DumpPluginXlsxTable {
+    pub fn build(
+        args: &DumpOptions,
+        _desc_index: &FileDescriptorIndex,
+    ) -> (Option<Box<dyn dump_plugin::DumpPluginInterface>>, bool) {
+        if args.output_xlsx.is_empty() {
+            return (None, false);
+        }
+
+        if !utility::check_output_path(&args.output_xlsx, "--output-xlsx", args.bin_file.len()) {
+            return (None, true);
+        }
+
+        let projection = match RowProjection::build(&args.select) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Parse --select failed, {}", e);
+                return (None, true);
+            }
+        };
+
+        (
+            Some(Box::new(DumpPluginXlsxTable {
+                content: VecDeque::new(),
+                projection,
+                single_sheet: args.xlsx_single_sheet,
+                write_to_path: args.output_xlsx.clone(),
+            })),
+            false,
+        )
+    }
+
+    fn save_workbook(
+        &self,
+        xlsx_table: &XlsxTableContent,
+        output_file: &str,
+    ) -> Result<(), XlsxError> {
+        let mut workbook = Workbook::new();
+        let header_format = Format::new().set_bold();
+        let mut used_names = HashSet::new();
+
+        for sheet in &xlsx_table.sheets {
+            let sheet_name = if self.single_sheet {
+                get_sheet_name(
+                    &std::path::Path::new(&xlsx_table.head.file_path)
+                        .file_stem()
+                        .map(|x| x.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    &mut used_names,
+                )
+            } else {
+                get_sheet_name(&sheet.data_source.sheet, &mut used_names)
+            };
+            write_sheet(
+                workbook.add_worksheet(),
+                &sheet_name,
+                &sheet.table,
+                &header_format,
+            )?;
+        }
+
+        // Workbook must have at least one sheet
+        if xlsx_table.sheets.is_empty() {
+            workbook.add_worksheet();
+        }
+
+        if let Some(parent) = std::path::Path::new(output_file).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        workbook.save(output_file)
+    }
+}
+
+impl dump_plugin::DumpPluginInterface for DumpPluginXlsxTable {
+    fn create_block(
+        &self,
+        data_source: Rc<dump_plugin::DumpPluginBlockDataSource>,
+    ) -> Option<Box<dyn dump_plugin::DumpPluginBlockInterface>> {
+        Some(Box::new(XlsxTableContent {
+            head: data_source,
+            sheets: Vec::new(),
+        }))
+    }
+
+    fn load_message(
+        &mut self,
+        block: &mut Box<dyn dump_plugin::DumpPluginBlockInterface>,
+        message: &dyn MessageDyn,
+        data_source: &dump_plugin::DumpPluginSheetDataSource,
+        _row_index: usize,
+    ) {
+        if let Some(rb) = block.as_any_mut().downcast_mut::<XlsxTableContent>() {
+            let sheet_index = if self.single_sheet {
+                rb.sheets.first().map(|_| 0)
+            } else {
+                rb.sheets
+                    .iter()
+                    .rposition(|x| x.data_source.as_ref() == data_source.item.as_ref())
+            };
+            let sheet_index = match sheet_index {
+                Some(x) => x,
+                None => {
+                    rb.sheets.push(XlsxTableSheet {
+                        data_source: data_source.into(),
+                        table: FlattenTable::new(),
+                    });
+                    rb.sheets.len() - 1
+                }
+            };
+
+            rb.sheets[sheet_index]
+                .table
+                .add_row(message, self.projection.as_ref());
+        } else {
+            error!(
+                "In DumpPluginXlsxTable::load_message, the block is not XlsxTableContent, ignore this message"
+            );
+        }
+    }
+
+    fn push_block(&mut self, block: Box<dyn dump_plugin::DumpPluginBlockInterface>) {
+        if let Ok(rb) = block.into_any().downcast::<XlsxTableContent>() {
+            self.content.push_back(rb);
+        } else {
+            error!(
+                "In DumpPluginXlsxTable::push_block, the block is not XlsxTableContent, ignore this message"
+            );
+        }
+    }
+
+    fn to_json(&self) -> Vec<json::JsonValue> {
+        Vec::new()
+    }
+
+    fn to_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn flush(&self) -> dump_plugin::DumpPluginFlushResult {
+        let mut ret = Ok(());
+        for xlsx_table in &self.content {
+            let output_file = utility::get_output_file_path(
+                &self.write_to_path,
+                &xlsx_table.head.file_path,
+                "xlsx",
+            );
+            if let Err(e) = self.save_workbook(xlsx_table, &output_file) {
+                error!("Try to write xlsx to {} failed, {}", output_file, e);
+                ret = Err(());
+            }
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+use protobuf::reflect::MessageDescriptor;
+use protobuf::{Message, MessageDyn, MessageFull};
+use xresloader_protocol::proto::pb_header_v3::Xresloader_datablocks;
+
+use super::file_descriptor_index::FileDescriptorIndex;
+use super::utility;
+
+/// Decoded rows of a binary file generated by xresloader
+pub struct XresloaderBinFile {
+    pub file_path: String,
+    pub data_blocks: Xresloader_datablocks,
+    pub message_descriptor: Rc<MessageDescriptor>,
+    pub rows: Vec<Box<dyn MessageDyn>>,
+}
+
+pub fn load_data_blocks(bin_file: &str) -> Result<Xresloader_datablocks, ()> {
+    debug!("Load xresloader output binary file: {}", bin_file);
+    let mut f = match std::fs::OpenOptions::new()
+        .read(true)
+        .write(false)
+        .open(bin_file)
+    {
+        Ok(f) => f,
+        Err(e) => {
+            error!("Try to open file {} failed, {}", bin_file, e);
+            return Err(());
+        }
+    };
+
+    let mut bin_data = Vec::new();
+    if let Err(e) = f.read_to_end(&mut bin_data) {
+        error!("Try to read file {} failed, {}", bin_file, e);
+        return Err(());
+    }
+
+    match Xresloader_datablocks::parse_from_bytes(&bin_data) {
+        Ok(x) => Ok(x),
+        Err(e) => {
+            error!(
+                "Parse {} from file {} failed, {}",
+                Xresloader_datablocks::descriptor().full_name(),
+                bin_file,
+                e
+            );
+            Err(())
+        }
+    }
+}
+
+pub fn save_data_blocks(bin_file: &str, data_blocks: &Xresloader_datablocks) -> Result<(), ()> {
+    let bin_data = match data_blocks.write_to_bytes() {
+        Ok(x) => x,
+        Err(e) => {
+            error!(
+                "Encode {} for file {} failed, {}",
+                Xresloader_datablocks::descriptor().full_name(),
+                bin_file,
+                e
+            );
+            return Err(());
+        }
+    };
+
+    let result = utility::create_output_file(bin_file).and_then(|mut f| f.write_all(&bin_data));
+    if let Err(e) = result {
+        error!("Try to write file {} failed, {}", bin_file, e);
+        return Err(());
+    }
+
+    Ok(())
+}
+
+impl XresloaderBinFile {
+    pub fn load(bin_file: &str, desc_index: &mut FileDescriptorIndex) -> Result<Self, ()> {
+        let data_blocks = load_data_blocks(bin_file)?;
+        if data_blocks.data_message_type.is_empty() {
+            error!(
+                "File {} has no data_message_type, please use xresloader 2.6 or upper",
+                bin_file
+            );
+            return Err(());
+        }
+
+        let message_descriptor =
+            match desc_index.build_message_descriptor(&data_blocks.data_message_type) {
+                Ok(x) => x,
+                Err(_) => {
+                    error!(
+                        "Build message descriptor {} failed",
+                        &data_blocks.data_message_type
+                    );
+                    return Err(());
+                }
+            };
+
+        let mut rows = Vec::with_capacity(data_blocks.data_block.len());
+        for (row_index, row_data_block) in data_blocks.data_block.iter().enumerate() {
+            match message_descriptor.parse_from_bytes(row_data_block) {
+                Ok(x) => rows.push(x),
+                Err(e) => {
+                    error!(
+                        "Parse row {} of file {} to message {} failed, {}",
+                        row_index + 1,
+                        bin_file,
+                        &data_blocks.data_message_type,
+                        e
+                    );
+                    return Err(());
+                }
+            }
+        }
+
+        Ok(XresloaderBinFile {
+            file_path: bin_file.to_string(),
+            data_blocks,
+            message_descriptor,
+            rows,
+        })
+    }
+}
@@ -0,0 +1,187 @@
+use json::JsonValue;
+
+const INDENT_SPACES: usize = 2;
+
+fn is_plain_scalar(input: &str) -> bool {
+    if input.is_empty() || input.trim() != input {
+        return false;
+    }
+
+    if input.starts_with([
+        '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@',
+        '`',
+    ]) {
+        return false;
+    }
+
+    if input.contains(": ") || input.contains(" #") || input.ends_with(':') {
+        return false;
+    }
+
+    if input.chars().any(|c| c.is_control()) {
+        return false;
+    }
+
+    // Keep strings which may be resolved as other types
+    let lower = input.to_lowercase();
+    if matches!(
+        lower.as_str(),
+        "~" | "null" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n"
+    ) {
+        return false;
+    }
+    if input.parse::<f64>().is_ok()
+        || lower.starts_with("0x")
+        || lower.starts_with("0o")
+        || lower.starts_with(".inf")
+        || lower.starts_with(".nan")
+    {
+        return false;
+    }
+
+    true
+}
+
+/// Multi-line strings are written as literal block scalars
+fn block_scalar_header(input: &str) -> Option<&'static str> {
+    if !input.contains('\n') || input.starts_with([' ', '\n']) {
+        return None;
+    }
+
+    if input.chars().any(|c| c.is_control() && c != '\n') {
+        return None;
+    }
+
+    if input.ends_with("\n\n") {
+        None
+    } else if input.ends_with('\n') {
+        Some("|")
+    } else {
+        Some("|-")
+    }
+}
+
+fn write_string_scalar(out: &mut String, input: &str) {
+    if is_plain_scalar(input) {
+        out.push_str(input);
+    } else {
+        // Json strings are also valid double-quoted scalars of yaml
+        out.push_str(&json::stringify(input));
+    }
+}
+
+fn write_scalar(out: &mut String, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Short(x) => write_string_scalar(out, x.as_str()),
+        JsonValue::String(x) => write_string_scalar(out, x.as_str()),
+        JsonValue::Object(_) => out.push_str("{}"),
+        JsonValue::Array(_) => out.push_str("[]"),
+        _ => out.push_str(&json::stringify(value.clone())),
+    }
+}
+
+fn write_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push(' ');
+    }
+}
+
+fn is_collection(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(x) => !x.is_empty(),
+        JsonValue::Array(x) => !x.is_empty(),
+        _ => false,
+    }
+}
+
+/// Write value after `key:` or `-` at indent
+fn write_child(out: &mut String, value: &JsonValue, indent: usize, in_sequence: bool) {
+    let child_indent = indent + INDENT_SPACES;
+
+    if let Some(header) = value.as_str().and_then(block_scalar_header) {
+        out.push(' ');
+        out.push_str(header);
+        out.push('\n');
+        for line in value
+            .as_str()
+            .unwrap_or_default()
+            .trim_end_matches('\n')
+            .split('\n')
+        {
+            if !line.is_empty() {
+                write_indent(out, child_indent);
+                out.push_str(line);
+            }
+            out.push('\n');
+        }
+        return;
+    }
+
+    if !is_collection(value) {
+        out.push(' ');
+        write_scalar(out, value);
+        out.push('\n');
+        return;
+    }
+
+    // Collections in sequence start at the same line of '-'
+    if in_sequence {
+        out.push(' ');
+    } else {
+        out.push('\n');
+    }
+    match value {
+        JsonValue::Object(x) => {
+            let mut entries: Vec<(&str, &JsonValue)> = x.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            write_mapping(out, &entries, child_indent, in_sequence);
+        }
+        JsonValue::Array(x) => write_sequence(out, x, child_indent, in_sequence),
+        _ => {}
+    }
+}
+
+fn write_mapping(
+    out: &mut String,
+    entries: &[(&str, &JsonValue)],
+    indent: usize,
+    first_inline: bool,
+) {
+    for (index, (key, value)) in entries.iter().enumerate() {
+        if index > 0 || !first_inline {
+            write_indent(out, indent);
+        }
+        write_string_scalar(out, key);
+        out.push(':');
+        write_child(out, value, indent, false);
+    }
+}
+
+fn write_sequence(out: &mut String, items: &[JsonValue], indent: usize, first_inline: bool) {
+    for (index, value) in items.iter().enumerate() {
+        if index > 0 || !first_inline {
+            write_indent(out, indent);
+        }
+        out.push('-');
+        write_child(out, value, indent, true);
+    }
+}
+
+/// Stringify json value as a yaml document in block style, keys of objects are sorted like ordered_generator
+pub fn stringify(root: &JsonValue) -> String {
+    let mut out = String::from("---\n");
+    match root {
+        JsonValue::Object(x) if !x.is_empty() => {
+            let mut entries: Vec<(&str, &JsonValue)> = x.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            write_mapping(&mut out, &entries, 0, false);
+        }
+        JsonValue::Array(x) if !x.is_empty() => write_sequence(&mut out, x, 0, false),
+        _ => {
+            write_scalar(&mut out, root);
+            out.push('\n');
+        }
+    }
+    out
+}